@@ -0,0 +1,91 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Reading, decoding and encoding image files, shared by the programs that
+//! work on files rather than JSON-LD streams.
+
+use crate::core::{Error, Result};
+use image::{DynamicImage, ImageFormat};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The encoded bytes of an input image and the file they were read from.
+#[derive(Clone, Debug)]
+pub struct Input {
+    pub data: Vec<u8>,
+    /// Canonical path of the file; `None` for stdin.
+    pub path: Option<PathBuf>,
+}
+
+impl Input {
+    /// Reads a file path, optionally prefixed by `file:` or `file://`, or
+    /// stdin for `None` or `-`.
+    pub fn read(url: Option<&str>) -> Result<Self> {
+        let Some(url) = url.filter(|&url| url != "-") else {
+            let mut data = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .map_err(|e| Error::Io {
+                    context: "reading from stdin",
+                    source: e,
+                })?;
+            return Ok(Self { data, path: None });
+        };
+
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        let path = path.strip_prefix("file:").unwrap_or(path);
+        let canonical = PathBuf::from(path).canonicalize().map_err(|e| Error::Io {
+            context: "resolving input path",
+            source: e,
+        })?;
+        let data = std::fs::read(&canonical).map_err(|e| Error::Io {
+            context: "reading input file",
+            source: e,
+        })?;
+        Ok(Self {
+            data,
+            path: Some(canonical),
+        })
+    }
+
+    /// The path for messages, or `[stdin]`.
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => "[stdin]".into(),
+        }
+    }
+
+    /// The `file:` URL of the path; `None` for stdin.
+    pub fn file_url(&self) -> Option<String> {
+        self.path
+            .as_ref()
+            .map(|path| format!("file:{}", path.display()))
+    }
+
+    /// Decodes the image, keeping its bit depth and alpha channel.
+    pub fn decode(&self) -> Result<DynamicImage> {
+        image::load_from_memory(&self.data).map_err(|e| Error::decoding(&self.name(), e))
+    }
+}
+
+/// Saves an image in the format given by the extension of `path`, creating
+/// missing parent directories. `image` is the image's `@id`, for errors.
+///
+/// The bit depth and alpha channel are kept where the format supports them;
+/// otherwise the image is converted to the closest color type it does, such
+/// as 16-bit RGBA to 8-bit RGB for JPEG.
+pub fn save(img: &DynamicImage, path: &Path, image: Option<&str>) -> Result<()> {
+    let format = ImageFormat::from_path(path).map_err(|e| Error::saving(path, image, e))?;
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent).map_err(|e| Error::Io {
+            context: "creating parent directory",
+            source: e,
+        })?;
+    }
+
+    img.save_with_format(path, format)
+        .map_err(|e| Error::saving(path, image, e))
+}
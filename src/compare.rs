@@ -0,0 +1,211 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Pixel-level comparison of RGB images.
+
+use crate::core::{Error, Result};
+use image::{DynamicImage, GrayImage, Rgb, RgbImage};
+use serde::Serialize;
+
+/// Side length of the SSIM window, in pixels.
+const SSIM_WINDOW: u32 = 8;
+
+/// Distance between consecutive SSIM windows, in pixels.
+const SSIM_STRIDE: u32 = 4;
+
+/// Similarity metrics of two equally-sized images.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub width: u32,
+    pub height: u32,
+    /// Peak signal-to-noise ratio in dB; `None` when the images are identical.
+    pub psnr: Option<f64>,
+    /// Mean structural similarity of the luma channels, in `-1.0..=1.0`.
+    pub ssim: f64,
+    /// Largest per-channel difference.
+    pub max_delta: u8,
+    /// Percentage of pixels whose largest channel difference exceeds the tolerance.
+    pub changed_pixels: f64,
+}
+
+impl Comparison {
+    /// Compares `a` against `b`, counting a pixel as changed if any channel
+    /// differs by more than `tolerance`.
+    pub fn compute(a: &RgbImage, b: &RgbImage, tolerance: u8) -> Result<Self> {
+        check_dimensions(a, b)?;
+
+        let mut sq_error = 0f64;
+        let mut max_delta = 0u8;
+        let mut changed = 0usize;
+        for (pa, pb) in a.pixels().zip(b.pixels()) {
+            let delta = pixel_delta(pa, pb);
+            max_delta = max_delta.max(delta);
+            changed += usize::from(delta > tolerance);
+            sq_error += (0..3)
+                .map(|c| (f64::from(pa.0[c]) - f64::from(pb.0[c])).powi(2))
+                .sum::<f64>();
+        }
+
+        let pixels = (a.width() as f64 * a.height() as f64).max(1.0);
+        let mse = sq_error / (pixels * 3.0);
+        let psnr = (mse > 0.0).then(|| 10.0 * (255.0 * 255.0 / mse).log10());
+
+        Ok(Self {
+            width: a.width(),
+            height: a.height(),
+            psnr,
+            ssim: ssim(&luma(a), &luma(b)),
+            max_delta,
+            changed_pixels: 100.0 * changed as f64 / pixels,
+        })
+    }
+}
+
+/// Renders the differences between `a` and `b`: unchanged pixels are a dimmed
+/// grayscale of `a`, changed pixels are red with intensity scaled by the delta.
+pub fn diff_image(a: &RgbImage, b: &RgbImage, tolerance: u8) -> Result<RgbImage> {
+    check_dimensions(a, b)?;
+
+    let gray = luma(a);
+    Ok(RgbImage::from_fn(a.width(), a.height(), |x, y| {
+        let delta = pixel_delta(a.get_pixel(x, y), b.get_pixel(x, y));
+        if delta > tolerance {
+            Rgb([128u8.saturating_add(delta / 2), 0, 0])
+        } else {
+            let g = gray.get_pixel(x, y).0[0] / 3;
+            Rgb([g, g, g])
+        }
+    }))
+}
+
+fn check_dimensions(a: &RgbImage, b: &RgbImage) -> Result<()> {
+    if a.dimensions() != b.dimensions() {
+        return Err(Error::InvalidDimensions(format!(
+            "cannot compare {}x{} with {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        )));
+    }
+    Ok(())
+}
+
+fn pixel_delta(a: &Rgb<u8>, b: &Rgb<u8>) -> u8 {
+    (0..3).map(|c| a.0[c].abs_diff(b.0[c])).max().unwrap_or(0)
+}
+
+fn luma(img: &RgbImage) -> GrayImage {
+    DynamicImage::ImageRgb8(img.clone()).into_luma8()
+}
+
+fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (w, h) = a.dimensions();
+    if w == 0 || h == 0 {
+        return 1.0;
+    }
+    let (win_w, win_h) = (SSIM_WINDOW.min(w), SSIM_WINDOW.min(h));
+
+    let mut total = 0f64;
+    let mut windows = 0usize;
+    for y in (0..=h - win_h).step_by(SSIM_STRIDE as usize) {
+        for x in (0..=w - win_w).step_by(SSIM_STRIDE as usize) {
+            let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0f64, 0f64, 0f64, 0f64, 0f64);
+            for dy in 0..win_h {
+                for dx in 0..win_w {
+                    let va = f64::from(a.get_pixel(x + dx, y + dy).0[0]);
+                    let vb = f64::from(b.get_pixel(x + dx, y + dy).0[0]);
+                    sa += va;
+                    sb += vb;
+                    saa += va * va;
+                    sbb += vb * vb;
+                    sab += va * vb;
+                }
+            }
+
+            let n = f64::from(win_w * win_h);
+            let (ma, mb) = (sa / n, sb / n);
+            let var_a = saa / n - ma * ma;
+            let var_b = sbb / n - mb * mb;
+            let cov = sab / n - ma * mb;
+
+            total += ((2.0 * ma * mb + C1) * (2.0 * cov + C2))
+                / ((ma * ma + mb * mb + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(width: u32, height: u32, f: impl Fn(u32, u32) -> u8) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let v = f(x, y);
+            Rgb([v, v, v])
+        })
+    }
+
+    fn checkerboard(dark: u8, light: u8) -> RgbImage {
+        gray(16, 16, |x, y| if (x + y) % 2 == 0 { dark } else { light })
+    }
+
+    #[test]
+    fn identical_images() {
+        let img = checkerboard(20, 200);
+        let cmp = Comparison::compute(&img, &img, 0).unwrap();
+        assert_eq!(cmp.psnr, None);
+        assert!((cmp.ssim - 1.0).abs() < 1e-12);
+        assert_eq!(cmp.max_delta, 0);
+        assert_eq!(cmp.changed_pixels, 0.0);
+        assert_eq!((cmp.width, cmp.height), (16, 16));
+    }
+
+    #[test]
+    fn known_noise() {
+        // Every channel off by 10, alternately up and down:
+        let a = gray(16, 16, |_, _| 100);
+        let b = checkerboard(90, 110);
+        let cmp = Comparison::compute(&a, &b, 5).unwrap();
+        let psnr = cmp.psnr.unwrap();
+        assert!((psnr - 10.0 * (255.0f64 * 255.0 / 100.0).log10()).abs() < 1e-9);
+        assert!((psnr - 28.13).abs() < 0.01);
+        assert_eq!(cmp.max_delta, 10);
+        assert_eq!(cmp.changed_pixels, 100.0);
+        assert!(cmp.ssim < 1.0);
+
+        let cmp = Comparison::compute(&a, &b, 10).unwrap();
+        assert_eq!(cmp.changed_pixels, 0.0);
+    }
+
+    #[test]
+    fn inverted_structure_has_negative_ssim() {
+        let cmp = Comparison::compute(&checkerboard(0, 255), &checkerboard(255, 0), 0).unwrap();
+        assert!(cmp.ssim < 0.0, "{}", cmp.ssim);
+    }
+
+    #[test]
+    fn rejects_different_dimensions() {
+        let (a, b) = (gray(2, 2, |_, _| 0), gray(2, 3, |_, _| 0));
+        assert!(matches!(
+            Comparison::compute(&a, &b, 0),
+            Err(Error::InvalidDimensions(_))
+        ));
+        assert!(diff_image(&a, &b, 0).is_err());
+    }
+
+    #[test]
+    fn diff_marks_changed_pixels_red() {
+        let a = gray(2, 1, |_, _| 90);
+        let b = gray(2, 1, |x, _| if x == 0 { 90 } else { 190 });
+        let diff = diff_image(&a, &b, 0).unwrap();
+        assert_eq!(diff.get_pixel(0, 0).0, [30, 30, 30]);
+        assert_eq!(diff.get_pixel(1, 0).0, [178, 0, 0]);
+    }
+}
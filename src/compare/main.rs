@@ -0,0 +1,258 @@
+// This is free and unencumbered software released into the public domain.
+
+#[cfg(not(feature = "std"))]
+compile_error!("asimov-image-compare requires the 'std' feature");
+
+use asimov_image_module::{
+    codec::Input,
+    compare::{Comparison, diff_image},
+    core::{Error, ErrorOptions, Result as CoreResult, handle_error, info_user, init_error_output},
+    pixels,
+    stream::JsonStream,
+};
+use asimov_module::SysexitsError::{self, *};
+use clap::{CommandFactory, Parser};
+use clientele::StandardOptions;
+use image::RgbImage;
+use know::classes::Image as KnowImage;
+use serde::Deserialize;
+use std::error::Error as StdError;
+use std::path::PathBuf;
+
+/// asimov-image-compare
+#[derive(Debug, Parser)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    /// Expected image: an image file or a JSON-LD Image line ("-" for stdin)
+    expected: String,
+
+    /// Actual image: an image file or a JSON-LD Image line ("-" for stdin)
+    actual: String,
+
+    /// Per-channel difference up to which a pixel still counts as unchanged
+    #[arg(short = 't', long, default_value_t = 0)]
+    tolerance: u8,
+
+    /// Write a visual diff image to this path (format inferred from extension)
+    #[arg(long, value_name = "FILE")]
+    diff: Option<PathBuf>,
+
+    /// Fail if PSNR (in dB) is below this value
+    #[arg(long, value_name = "DB")]
+    min_psnr: Option<f64>,
+
+    /// Fail if SSIM is below this value
+    #[arg(long, value_name = "SSIM")]
+    min_ssim: Option<f64>,
+
+    /// Fail if the percentage of changed pixels exceeds this value
+    #[arg(long, value_name = "PERCENT")]
+    max_changed: Option<f64>,
+}
+
+pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
+    // Load environment variables from `.env`:
+    asimov_module::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Stdin can only be read once:
+    if options.expected == "-" && options.actual == "-" {
+        Options::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "only one of EXPECTED and ACTUAL can be read from stdin",
+            )
+            .exit();
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
+    let exit_code = match run_compare(&options) {
+        Ok(exit_code) => exit_code,
+        Err(err) => handle_error(&err, &options.flags),
+    };
+
+    Ok(exit_code)
+}
+
+fn run_compare(opts: &Options) -> CoreResult<SysexitsError> {
+    let flags = &opts.flags;
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::compare",
+        expected = %opts.expected,
+        actual = %opts.actual,
+        tolerance = opts.tolerance,
+        "starting compare"
+    );
+
+    let expected = load_image(&opts.expected)?;
+    let actual = load_image(&opts.actual)?;
+
+    let comparison = Comparison::compute(&expected, &actual, opts.tolerance)?;
+
+    if let Some(path) = &opts.diff {
+        diff_image(&expected, &actual, opts.tolerance)?
+            .save(path)
+            .map_err(|e| Error::saving(path, None, e))?;
+    }
+
+    let mut failures = Vec::new();
+    if let Some(min) = opts.min_psnr
+        && comparison.psnr.is_some_and(|psnr| psnr < min)
+    {
+        failures.push(format!("PSNR below {min} dB"));
+    }
+    if let Some(min) = opts.min_ssim
+        && comparison.ssim < min
+    {
+        failures.push(format!("SSIM below {min}"));
+    }
+    if let Some(max) = opts.max_changed
+        && comparison.changed_pixels > max
+    {
+        failures.push(format!("more than {max}% of pixels changed"));
+    }
+
+    let mut report = serde_json::to_value(&comparison).map_err(|e| Error::JsonLd(e.to_string()))?;
+    if let Some(obj) = report.as_object_mut() {
+        obj.insert("pass".into(), failures.is_empty().into());
+    }
+    println!("{report}");
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::compare",
+        psnr = ?comparison.psnr,
+        ssim = comparison.ssim,
+        max_delta = comparison.max_delta,
+        changed_pixels = comparison.changed_pixels,
+        "finished compare"
+    );
+
+    if failures.is_empty() {
+        Ok(EX_OK)
+    } else {
+        info_user(flags, &format!("images differ: {}", failures.join(", ")));
+        Ok(EX_DATAERR)
+    }
+}
+
+/// Loads an image file, or the first JSON-LD Image in a file, as RGB.
+fn load_image(path: &str) -> CoreResult<RgbImage> {
+    decode_input(&Input::read(Some(path))?)
+}
+
+fn decode_input(input: &Input) -> CoreResult<RgbImage> {
+    let data = &input.data;
+
+    if matches!(data.trim_ascii_start().first(), Some(b'{' | b'[')) {
+        let record = JsonStream::new(&data[..])
+            .next()
+            .ok_or_else(|| Error::Parse {
+                line: None,
+                message: "no Image found in input".into(),
+            })??;
+        let img = KnowImage::deserialize(&record.value).map_err(|e| Error::Parse {
+            line: Some(record.line),
+            message: e.to_string(),
+        })?;
+        return pixels::to_rgb_image(&img);
+    }
+
+    Ok(input.decode()?.to_rgb8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(data: &[u8]) -> Input {
+        Input {
+            data: data.to_vec(),
+            path: None,
+        }
+    }
+
+    const RED_PIXEL: &str = r#"{"@type": "Image", "@id": "_:red", "width": 1, "height": 1, "data": "data:image/rgb;base64,/wAA"}"#;
+
+    #[test]
+    fn decodes_a_json_ld_image_line() {
+        let img = decode_input(&input(RED_PIXEL.as_bytes())).unwrap();
+        assert_eq!(img.dimensions(), (1, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0]);
+    }
+
+    #[test]
+    fn decodes_the_first_image_of_a_json_ld_document() {
+        let data = format!("  [\n{RED_PIXEL},\n{RED_PIXEL}\n]");
+        let img = decode_input(&input(data.as_bytes())).unwrap();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0]);
+
+        let data = format!(r#"{{"@context": "https://schema.org/", "@graph": [{RED_PIXEL}]}}"#);
+        assert!(decode_input(&input(data.as_bytes())).is_ok());
+    }
+
+    #[test]
+    fn rejects_json_without_an_image() {
+        let errors = [
+            decode_input(&input(b"[]")).unwrap_err(),
+            decode_input(&input(br#"{"@type": "Person"}"#)).unwrap_err(),
+            decode_input(&input(br#"{"@type": "Image", "width": oops}"#)).unwrap_err(),
+            decode_input(&input(br#"{"@type": "Image", "width": 2, "height": 2, "data": "data:image/rgb;base64,/wAA"}"#))
+                .unwrap_err(),
+        ];
+        for error in errors {
+            assert!(
+                matches!(error, Error::Parse { .. } | Error::InvalidBuffer(_)),
+                "{error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_image_files() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        RgbImage::from_pixel(2, 1, image::Rgb([0, 0, 255]))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        let img = decode_input(&input(png.get_ref())).unwrap();
+        assert_eq!(img.dimensions(), (2, 1));
+        assert_eq!(img.get_pixel(1, 0).0, [0, 0, 255]);
+
+        assert!(matches!(
+            decode_input(&input(b"not an image")),
+            Err(Error::UnsupportedFormat { .. })
+        ));
+    }
+}
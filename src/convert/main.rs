@@ -0,0 +1,363 @@
+// This is free and unencumbered software released into the public domain.
+
+#[cfg(not(feature = "std"))]
+compile_error!("asimov-image-convert requires the 'std' feature");
+
+use asimov_image_module::{
+    codec::{self, Input},
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, info_user,
+        init_error_output, warn_input_error,
+    },
+    tally::{FailureOptions, Tally},
+};
+use asimov_module::SysexitsError::{self, *};
+use clap::{CommandFactory, Parser};
+use clientele::StandardOptions;
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// asimov-image-convert
+#[derive(Debug, Parser)]
+// Without any of these, every output would be its own input:
+#[command(group(
+    clap::ArgGroup::new("target")
+        .required(true)
+        .multiple(true)
+        .args(["output", "out_dir", "to", "map"])
+))]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    failures: FailureOptions,
+
+    /// Input image files ("-" for stdin, with --output)
+    #[arg(value_name = "FILES", required = true)]
+    files: Vec<String>,
+
+    /// Output file for a single input.
+    /// Format is inferred from the file extension (e.g., .png, .webp, .tiff).
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["out_dir", "to", "map"])]
+    output: Option<PathBuf>,
+
+    /// Directory receiving the converted files, instead of each input's own
+    #[arg(short = 'O', long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Output extension for every input (e.g., webp)
+    #[arg(short = 't', long, value_name = "EXT", value_parser = parse_extension)]
+    to: Option<String>,
+
+    /// Output extension for inputs with extension FROM (e.g., tiff=webp); repeatable,
+    /// and preferred over --to
+    #[arg(short = 'm', long, value_name = "FROM=TO", value_parser = parse_mapping)]
+    map: Vec<(String, String)>,
+}
+
+pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
+    // Load environment variables from `.env`:
+    asimov_module::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    if options.output.is_some() && options.files.len() > 1 {
+        Options::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--output takes a single input; use --out-dir for several",
+            )
+            .exit();
+    }
+    // Stdin has no name to derive the output's from:
+    if options.output.is_none() && options.files.iter().any(|file| file == "-") {
+        Options::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "reading stdin requires --output",
+            )
+            .exit();
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
+    let exit_code = match run_convert(&options) {
+        Ok(()) => EX_OK,
+        Err(err) => handle_error(&err, &options.flags),
+    };
+
+    Ok(exit_code)
+}
+
+fn run_convert(opts: &Options) -> CoreResult<()> {
+    let flags = &opts.flags;
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::convert",
+        files = opts.files.len(),
+        out_dir = ?opts.out_dir,
+        to = ?opts.to,
+        "starting convert"
+    );
+
+    let tally = Tally::new(&opts.failures);
+    let result = convert_files(opts, &tally);
+    tally.report(flags, "converted");
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::convert",
+        "convert exiting"
+    );
+
+    result
+}
+
+/// Converts every file, skipping the ones that fail until the failure budget
+/// is exceeded. Fails with the last error if no file could be converted.
+fn convert_files(opts: &Options, tally: &Tally) -> CoreResult<()> {
+    let flags = &opts.flags;
+    let mut last_error = None;
+    let mut outputs = HashSet::new();
+
+    for file in &opts.files {
+        let input_context = InputContext {
+            line: None,
+            image: Some(file),
+        };
+        match convert_file(opts, file, tally, &mut outputs) {
+            Ok(output) => {
+                tally.record_processed();
+                info_user(flags, &format!("converted {file} to {}", output.display()));
+            },
+            Err(e) => {
+                warn_input_error(flags, "failed to convert image", &e, input_context);
+                if tally.count_failure() {
+                    return Err(e);
+                }
+                last_error = Some(e);
+            },
+        }
+    }
+
+    match last_error {
+        Some(err) if tally.processed() == 0 => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Decodes one file and encodes it to its output path, which is returned.
+/// `outputs` holds the paths written so far, which are not overwritten.
+fn convert_file(
+    opts: &Options,
+    file: &str,
+    tally: &Tally,
+    outputs: &mut HashSet<PathBuf>,
+) -> CoreResult<PathBuf> {
+    let input = Input::read(Some(file))?;
+    tally.record_read();
+
+    let output = output_path(opts, &input)?;
+    check_output(&input, &output, outputs)?;
+
+    let img = input.decode()?;
+    tally.record_parsed();
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::debug!(
+        target: "asimov_image_module::convert",
+        input = %input.name(),
+        output = %output.display(),
+        color = ?img.color(),
+        width = img.width(),
+        height = img.height(),
+        "decoded image"
+    );
+
+    codec::save(&img, &output, input.file_url().as_deref())?;
+    outputs.insert(output.clone());
+    Ok(output)
+}
+
+/// Refuses an output that would replace its input or an earlier output.
+fn check_output(input: &Input, output: &Path, outputs: &HashSet<PathBuf>) -> CoreResult<()> {
+    let conflict = if input.path.is_some() && input.path == output.canonicalize().ok() {
+        "is the input file"
+    } else if outputs.contains(output) {
+        "is the output of an earlier input"
+    } else {
+        return Ok(());
+    };
+    Err(Error::Io {
+        context: "writing the output",
+        source: io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' {conflict}", output.display()),
+        ),
+    })
+}
+
+/// The `--output` file, or the input's file name with its extension mapped,
+/// in `--out-dir` or next to the input.
+fn output_path(opts: &Options, input: &Input) -> CoreResult<PathBuf> {
+    if let Some(output) = &opts.output {
+        return Ok(output.clone());
+    }
+    let Some(path) = &input.path else {
+        return Err(Error::Other("reading stdin requires --output".into()));
+    };
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let target = opts
+        .map
+        .iter()
+        .find(|(from, _)| *from == extension)
+        .map(|(_, to)| to)
+        .or(opts.to.as_ref());
+
+    let file_name = match target {
+        Some(target) => Path::new(path.file_stem().unwrap_or_default()).with_extension(target),
+        None => PathBuf::from(path.file_name().unwrap_or_default()),
+    };
+    let dir = match &opts.out_dir {
+        Some(dir) => dir.as_path(),
+        None => path.parent().unwrap_or(Path::new("")),
+    };
+    Ok(dir.join(file_name))
+}
+
+/// Accepts "webp" or ".webp", case-insensitively.
+fn parse_extension(s: &str) -> Result<String, String> {
+    let ext = s.trim().trim_start_matches('.').to_lowercase();
+    if ext.is_empty() || ext.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid extension '{s}'. Use a name such as webp"));
+    }
+    Ok(ext)
+}
+
+/// Accepts "tiff=webp", with either side optionally starting with a dot.
+fn parse_mapping(s: &str) -> Result<(String, String), String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid mapping '{s}'. Use FROM=TO (e.g., tiff=webp)"))?;
+    Ok((parse_extension(from)?, parse_extension(to)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        Options::try_parse_from(["asimov-image-convert"].iter().chain(args)).unwrap()
+    }
+
+    fn input(path: &str) -> Input {
+        Input {
+            data: Vec::new(),
+            path: Some(PathBuf::from(path)),
+        }
+    }
+
+    fn output(args: &[&str], path: &str) -> PathBuf {
+        output_path(&options(args), &input(path)).unwrap()
+    }
+
+    #[test]
+    fn parses_extensions_and_mappings() {
+        assert_eq!(parse_extension(".WebP").unwrap(), "webp");
+        assert!(parse_extension("").is_err());
+        assert!(parse_extension("tar.gz").is_err());
+        assert!(parse_extension("a/b").is_err());
+        assert_eq!(
+            parse_mapping("TIFF=.webp").unwrap(),
+            ("tiff".into(), "webp".into())
+        );
+        assert!(parse_mapping("tiff").is_err());
+        assert!(parse_mapping("tiff=").is_err());
+    }
+
+    #[test]
+    fn requires_an_output_option() {
+        let error = Options::try_parse_from(["asimov-image-convert", "a.tiff"]).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            clap::error::ErrorKind::MissingRequiredArgument
+        );
+    }
+
+    #[test]
+    fn maps_extensions_before_applying_to() {
+        let args = ["a", "-t", "png", "-m", "tiff=webp", "--map", "bmp=jpg"];
+        assert_eq!(output(&args, "/in/a.tiff"), Path::new("/in/a.webp"));
+        assert_eq!(output(&args, "/in/b.BMP"), Path::new("/in/b.jpg"));
+        assert_eq!(output(&args, "/in/c.gif"), Path::new("/in/c.png"));
+        assert_eq!(output(&args, "/in/d"), Path::new("/in/d.png"));
+    }
+
+    #[test]
+    fn writes_into_the_output_directory() {
+        let args = ["a", "-O", "out"];
+        assert_eq!(output(&args, "/in/a.tiff"), Path::new("out/a.tiff"));
+        let args = ["a", "-O", "out", "-t", "webp"];
+        assert_eq!(output(&args, "/in/a.tiff"), Path::new("out/a.webp"));
+        let args = ["a", "--output", "b.png"];
+        assert_eq!(output(&args, "/in/a.tiff"), Path::new("b.png"));
+    }
+
+    #[test]
+    fn refuses_to_replace_an_input_or_earlier_output() {
+        let dir = std::env::temp_dir().join(format!("convert-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.png"), b"").unwrap();
+        let input = Input {
+            data: Vec::new(),
+            path: Some(dir.join("a.png").canonicalize().unwrap()),
+        };
+
+        let mut outputs = HashSet::new();
+        // Without --to or a matching --map, the output is the input:
+        let same = output_path(&options(&["a", "-m", "bmp=png"]), &input).unwrap();
+        assert!(check_output(&input, &same, &outputs).is_err());
+
+        let webp = output_path(&options(&["a", "-t", "webp"]), &input).unwrap();
+        assert!(check_output(&input, &webp, &outputs).is_ok());
+        outputs.insert(webp.clone());
+        let error = check_output(&input, &webp, &outputs).unwrap_err();
+        assert!(error.to_string().contains("earlier input"), "{error}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,7 +2,12 @@
 
 use asimov_module::SysexitsError::{self, *};
 use clientele::StandardOptions;
+use serde_json::{Map, Value, json};
 use std::error::Error as StdError;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use thiserror::Error;
 
 /// Result type used by this crate.
@@ -21,6 +26,40 @@ pub enum Error {
     #[error("failed to decode image data: {0}")]
     Decode(#[from] image::ImageError),
 
+    #[error("unsupported image format for '{path}': {reason}")]
+    UnsupportedFormat {
+        /// The file path or URL whose format is not supported.
+        path: String,
+        reason: String,
+    },
+
+    #[error("failed to encode image{} to '{path}': {source}", describe_image(.image))]
+    Encode {
+        /// The output file path.
+        path: String,
+        /// The `@id` of the image being encoded.
+        image: Option<String>,
+        #[source]
+        source: image::ImageError,
+    },
+
+    #[error("image exceeds decoder limits{}: {reason}", describe_image(.image))]
+    Limits {
+        /// The `@id`, path or URL of the image.
+        image: Option<String>,
+        reason: String,
+    },
+
+    #[error("failed to parse input{}: {message}", describe_line(.line))]
+    Parse {
+        /// 1-based line number of the input record.
+        line: Option<usize>,
+        message: String,
+    },
+
+    #[error("window error: {0}")]
+    Window(String),
+
     #[error("invalid resize dimensions: {0}")]
     InvalidDimensions(String),
 
@@ -30,10 +69,224 @@ pub enum Error {
     #[error("JSON-LD conversion failed: {0}")]
     JsonLd(String),
 
+    #[error("no input: {0}")]
+    NoInput(String),
+
     #[error("{0}")]
     Other(String),
 }
 
+impl Error {
+    /// A stable, machine-readable name for the variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io { .. } => "io",
+            Error::Decode(_) => "decode",
+            Error::UnsupportedFormat { .. } => "unsupported_format",
+            Error::Encode { .. } => "encode",
+            Error::Limits { .. } => "limits",
+            Error::Parse { .. } => "parse",
+            Error::Window(_) => "window",
+            Error::InvalidDimensions(_) => "invalid_dimensions",
+            Error::InvalidBuffer(_) => "invalid_buffer",
+            Error::JsonLd(_) => "jsonld",
+            Error::NoInput(_) => "no_input",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Classifies a failure to decode the image at `path` (a file path or URL).
+    pub fn decoding(path: &str, error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::Unsupported(e) => Error::UnsupportedFormat {
+                path: path.into(),
+                reason: e.to_string(),
+            },
+            image::ImageError::Limits(e) => Error::Limits {
+                image: Some(path.into()),
+                reason: e.to_string(),
+            },
+            error => Error::Decode(error),
+        }
+    }
+
+    /// Classifies a failure to save the image identified by `image` to `path`.
+    pub fn saving(path: &Path, image: Option<&str>, error: image::ImageError) -> Self {
+        let path = path.display().to_string();
+        match error {
+            image::ImageError::Unsupported(e) => Error::UnsupportedFormat {
+                path,
+                reason: e.to_string(),
+            },
+            image::ImageError::Limits(e) => Error::Limits {
+                image: image.map(String::from),
+                reason: e.to_string(),
+            },
+            error => Error::Encode {
+                path,
+                image: image.map(String::from),
+                source: error,
+            },
+        }
+    }
+
+    /// The input line the error refers to, if known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Error::Parse { line, .. } => *line,
+            _ => None,
+        }
+    }
+
+    /// The `@id` of the image the error refers to, if known.
+    pub fn image(&self) -> Option<&str> {
+        match self {
+            Error::Encode { image, .. } | Error::Limits { image, .. } => image.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The file path or URL the error refers to, if known.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::UnsupportedFormat { path, .. } | Error::Encode { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+fn describe_image(image: &Option<String>) -> String {
+    image
+        .as_deref()
+        .map(|image| format!(" '{image}'"))
+        .unwrap_or_default()
+}
+
+fn describe_line(line: &Option<usize>) -> String {
+    line.map(|line| format!(" on line {line}"))
+        .unwrap_or_default()
+}
+
+/// How failures are reported in addition to the human-readable messages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ErrorFormat {
+    /// Human-readable `ERROR:` and `WARN:` lines only
+    #[default]
+    Text,
+    /// Also one JSON record per error and warning
+    Json,
+}
+
+/// Command-line options for structured error output, shared by all programs.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct ErrorOptions {
+    /// Error report format; `json` writes one record per failure, regardless of verbosity
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_enum, value_name = "FORMAT", default_value_t = ErrorFormat::Text)
+    )]
+    pub error_format: ErrorFormat,
+
+    /// Append JSON error records to FILE instead of stderr (implies `--error-format json`)
+    #[cfg_attr(feature = "cli", arg(long, value_name = "FILE"))]
+    pub error_file: Option<PathBuf>,
+}
+
+/// Where in the input a failure occurred, for structured error records.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputContext<'a> {
+    /// 1-based line number of the input record.
+    pub line: Option<usize>,
+    /// The `@id` of the image being processed.
+    pub image: Option<&'a str>,
+}
+
+/// Destination of JSON error records, once configured.
+static ERROR_OUTPUT: OnceLock<ErrorOutput> = OnceLock::new();
+
+enum ErrorOutput {
+    Stderr,
+    File(Mutex<File>),
+}
+
+/// Configure structured error output from the command-line options.
+/// Call once at startup; later calls are ignored.
+pub fn init_error_output(opts: &ErrorOptions) -> Result<()> {
+    let output = match (&opts.error_file, opts.error_format) {
+        (Some(path), _) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| Error::Io {
+                    context: "opening error file",
+                    source: e,
+                })?;
+            ErrorOutput::File(Mutex::new(file))
+        },
+        (None, ErrorFormat::Json) => ErrorOutput::Stderr,
+        (None, ErrorFormat::Text) => return Ok(()),
+    };
+    let _ = ERROR_OUTPUT.set(output);
+    Ok(())
+}
+
+/// Write a JSON error record, if structured error output is configured.
+///
+/// Record fields: `level` (`error` or `warning`), `kind` (the `Error` variant,
+/// or `parse`/`io`/`other` for foreign errors), `message`, `error`, and when
+/// known `context`, `path`, `line` and `image`.
+pub fn record_error(level: &str, msg: &str, error: &(dyn StdError + 'static), input: InputContext) {
+    let Some(output) = ERROR_OUTPUT.get() else {
+        return;
+    };
+
+    let mut record = Map::new();
+    record.insert("level".into(), json!(level));
+    record.insert("kind".into(), json!(error_kind(error)));
+    record.insert("message".into(), json!(msg));
+    record.insert("error".into(), json!(error.to_string()));
+    let err = error.downcast_ref::<Error>();
+    if let Some(Error::Io { context, .. }) = err {
+        record.insert("context".into(), json!(context));
+    }
+    if let Some(path) = err.and_then(Error::path) {
+        record.insert("path".into(), json!(path));
+    }
+    if let Some(line) = input.line.or_else(|| err.and_then(Error::line)) {
+        record.insert("line".into(), json!(line));
+    }
+    if let Some(image) = input.image.or_else(|| err.and_then(Error::image)) {
+        record.insert("image".into(), json!(image));
+    }
+    let line = Value::Object(record).to_string();
+
+    match output {
+        ErrorOutput::Stderr => {
+            let _ = writeln!(std::io::stderr().lock(), "{line}");
+        },
+        ErrorOutput::File(file) => {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{line}").and_then(|_| file.flush());
+            }
+        },
+    }
+}
+
+fn error_kind(error: &(dyn StdError + 'static)) -> &'static str {
+    if let Some(err) = error.downcast_ref::<Error>() {
+        err.kind()
+    } else if error.is::<serde_json::Error>() {
+        "parse"
+    } else if error.is::<std::io::Error>() {
+        "io"
+    } else {
+        "other"
+    }
+}
+
 /// Helper to construct a boxed error from a string.
 pub fn err_msg<M: Into<String>>(m: M) -> Box<dyn StdError> {
     m.into().into()
@@ -52,8 +305,11 @@ pub fn handle_error(err: &Error, flags: &StandardOptions) -> SysexitsError {
         }
     }
 
-    // Human-readable stderr output.
-    report_error(err, flags);
+    // Human-readable stderr output, unless stderr carries JSON records:
+    record_error("error", "command failed", err, InputContext::default());
+    if !matches!(ERROR_OUTPUT.get(), Some(ErrorOutput::Stderr)) {
+        report_error(err, flags);
+    }
 
     // Exit code.
     map_error_to_sysexit(err)
@@ -93,7 +349,22 @@ pub fn warn_user(flags: &StandardOptions, msg: &str) {
 ///   - verbose == 0: no stderr output
 ///   - verbose == 1: `WARN: msg`
 ///   - verbose >= 2 or debug: `WARN: msg: error`
-pub fn warn_user_with_error(flags: &StandardOptions, msg: &str, error: &dyn StdError) {
+pub fn warn_user_with_error(flags: &StandardOptions, msg: &str, error: &(dyn StdError + 'static)) {
+    warn_input_error(flags, msg, error, InputContext::default());
+}
+
+/// Log a warning about a failure on an input record, with its location.
+///
+/// Behaves like `warn_user_with_error`, and additionally writes a JSON error
+/// record carrying the line number and image id if configured.
+pub fn warn_input_error(
+    flags: &StandardOptions,
+    msg: &str,
+    error: &(dyn StdError + 'static),
+    input: InputContext,
+) {
+    record_error("warning", msg, error, input);
+
     if flags.debug || flags.verbose >= 2 {
         eprintln!("WARN: {msg}: {error}");
     } else if flags.verbose >= 1 {
@@ -109,8 +380,6 @@ pub fn warn_user_with_error(flags: &StandardOptions, msg: &str, error: &dyn StdE
 }
 
 fn report_error(err: &Error, flags: &StandardOptions) {
-    use std::io::Write;
-
     let mut stderr = std::io::stderr();
     // Always show the top-level error:
     let _ = writeln!(stderr, "ERROR: {err}");
@@ -129,8 +398,152 @@ fn map_error_to_sysexit(err: &Error) -> SysexitsError {
     match err {
         Error::Io { .. } => EX_IOERR,
         Error::Decode(_) | Error::InvalidBuffer(_) => EX_DATAERR,
+        Error::Limits { .. } | Error::Parse { .. } => EX_DATAERR,
+        Error::UnsupportedFormat { .. } => EX_USAGE,
+        Error::Encode { .. } => EX_CANTCREAT,
+        Error::Window(_) => EX_UNAVAILABLE,
         Error::InvalidDimensions(_) => EX_USAGE,
         Error::JsonLd(_) => EX_SOFTWARE,
+        Error::NoInput(_) => EX_NOINPUT,
         Error::Other(_) => EX_SOFTWARE,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::error::{
+        DecodingError, EncodingError, ImageFormatHint, LimitError, LimitErrorKind,
+        UnsupportedError, UnsupportedErrorKind,
+    };
+
+    fn unsupported() -> image::ImageError {
+        image::ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+            ImageFormatHint::Unknown,
+            UnsupportedErrorKind::Format(ImageFormatHint::Unknown),
+        ))
+    }
+
+    fn limits() -> image::ImageError {
+        image::ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError))
+    }
+
+    fn io_error() -> Error {
+        Error::Io {
+            context: "reading input",
+            source: std::io::Error::other("broken pipe"),
+        }
+    }
+
+    fn parse_error() -> Error {
+        Error::Parse {
+            line: Some(3),
+            message: "expected value".into(),
+        }
+    }
+
+    #[test]
+    fn maps_every_variant_to_a_sysexit() {
+        let decoding =
+            image::ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, "corrupt"));
+        let encoding =
+            image::ImageError::Encoding(EncodingError::new(ImageFormatHint::Unknown, "disk full"));
+        let cases = [
+            (io_error(), EX_IOERR),
+            (Error::Decode(decoding), EX_DATAERR),
+            (
+                Error::UnsupportedFormat {
+                    path: "a.xyz".into(),
+                    reason: "unknown".into(),
+                },
+                EX_USAGE,
+            ),
+            (
+                Error::Encode {
+                    path: "a.png".into(),
+                    image: None,
+                    source: encoding,
+                },
+                EX_CANTCREAT,
+            ),
+            (
+                Error::Limits {
+                    image: None,
+                    reason: "too large".into(),
+                },
+                EX_DATAERR,
+            ),
+            (parse_error(), EX_DATAERR),
+            (Error::Window("no display".into()), EX_UNAVAILABLE),
+            (Error::InvalidDimensions("0x0".into()), EX_USAGE),
+            (Error::InvalidBuffer("short".into()), EX_DATAERR),
+            (Error::JsonLd("bad".into()), EX_SOFTWARE),
+            (Error::NoInput("empty".into()), EX_NOINPUT),
+            (Error::Other("other".into()), EX_SOFTWARE),
+        ];
+        for (error, code) in cases {
+            assert_eq!(map_error_to_sysexit(&error), code, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn classifies_decoding_errors() {
+        let error = Error::decoding("file:/a.xyz", unsupported());
+        assert!(matches!(&error, Error::UnsupportedFormat { path, .. } if path == "file:/a.xyz"));
+        assert_eq!(map_error_to_sysexit(&error), EX_USAGE);
+        assert_eq!(error.path(), Some("file:/a.xyz"));
+
+        let error = Error::decoding("file:/big.png", limits());
+        assert!(matches!(error, Error::Limits { .. }));
+        assert_eq!(error.image(), Some("file:/big.png"));
+        assert_eq!(map_error_to_sysexit(&error), EX_DATAERR);
+
+        let corrupt =
+            image::ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, "corrupt"));
+        assert!(matches!(
+            Error::decoding("file:/a.png", corrupt),
+            Error::Decode(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_saving_errors() {
+        let path = Path::new("out/a.xyz");
+        let error = Error::saving(path, Some("_:a"), unsupported());
+        assert!(matches!(&error, Error::UnsupportedFormat { path, .. } if path == "out/a.xyz"));
+        assert_eq!(map_error_to_sysexit(&error), EX_USAGE);
+
+        let error = Error::saving(path, Some("_:a"), limits());
+        assert!(matches!(error, Error::Limits { .. }));
+        assert_eq!(error.image(), Some("_:a"));
+
+        let encoding =
+            image::ImageError::Encoding(EncodingError::new(ImageFormatHint::Unknown, "disk full"));
+        let error = Error::saving(path, Some("_:a"), encoding);
+        assert!(matches!(error, Error::Encode { .. }));
+        assert_eq!(error.path(), Some("out/a.xyz"));
+        assert_eq!(error.image(), Some("_:a"));
+        assert_eq!(map_error_to_sysexit(&error), EX_CANTCREAT);
+    }
+
+    #[test]
+    fn names_kinds_and_context() {
+        assert_eq!(io_error().kind(), "io");
+        assert_eq!(parse_error().kind(), "parse");
+        assert_eq!(Error::Window(String::new()).kind(), "window");
+        assert_eq!(Error::NoInput(String::new()).kind(), "no_input");
+        assert_eq!(
+            Error::decoding("a", unsupported()).kind(),
+            "unsupported_format"
+        );
+        assert_eq!(Error::decoding("a", limits()).kind(), "limits");
+
+        assert_eq!(parse_error().line(), Some(3));
+        assert_eq!(io_error().line(), None);
+        assert_eq!(parse_error().image(), None);
+        assert_eq!(
+            parse_error().to_string(),
+            "failed to parse input on line 3: expected value"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// This is free and unencumbered software released into the public domain.
+
+#[cfg(not(feature = "std"))]
+compile_error!("asimov-image-dedup requires the 'std' feature");
+
+use asimov_image_module::{
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, init_error_output,
+        warn_input_error, warn_user_with_error,
+    },
+    hash::{PerceptualHash, format_hash, hamming_distance, parse_hash},
+    jsonld::{self, ContextOptions},
+    pixels,
+    stream::JsonStream,
+};
+use asimov_module::SysexitsError::{self, *};
+use clap::{Parser, ValueEnum};
+use clientele::StandardOptions;
+use know::classes::Image as KnowImage;
+use serde::Deserialize;
+use serde_json::Value;
+use std::error::Error as StdError;
+use std::io::{self, Write};
+
+/// asimov-image-dedup
+#[derive(Debug, Parser)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    jsonld: ContextOptions,
+
+    /// Perceptual hash used to compare images
+    #[arg(short = 'a', long, value_enum, default_value_t = PerceptualHash::Phash)]
+    algorithm: PerceptualHash,
+
+    /// Maximum Hamming distance (in bits) for two images to be near-duplicates
+    #[arg(short = 't', long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(0..=64))]
+    threshold: u32,
+
+    /// Emit only the first image of each cluster, or every image annotated with its cluster
+    #[arg(short = 'm', long, value_enum, default_value_t = Mode::Unique)]
+    mode: Mode,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Mode {
+    /// Drop near-duplicates of previously seen images
+    Unique,
+    /// Keep every image and add `cluster`, `duplicateOf` and `hammingDistance`
+    Annotate,
+}
+
+/// A group of near-duplicate images, represented by its first member.
+struct Cluster {
+    hash: u64,
+    id: Option<String>,
+}
+
+pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
+    // Load environment variables from `.env`:
+    asimov_module::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
+    let exit_code = match run_dedup(&options) {
+        Ok(()) => EX_OK,
+        Err(err) => handle_error(&err, &options.flags),
+    };
+
+    Ok(exit_code)
+}
+
+fn run_dedup(opts: &Options) -> CoreResult<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let flags = &opts.flags;
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::dedup",
+        algorithm = ?opts.algorithm,
+        threshold = opts.threshold,
+        mode = ?opts.mode,
+        "starting dedup"
+    );
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+
+    for record in JsonStream::new(stdin.lock()) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e @ Error::Parse { .. }) => {
+                warn_user_with_error(flags, "failed to parse Image JSON-LD", &e);
+                continue;
+            },
+            Err(e) => {
+                warn_user_with_error(flags, "stdin read error", &e);
+                break;
+            },
+        };
+        let input = InputContext {
+            line: Some(record.line),
+            image: None,
+        };
+        let mut value = record.value;
+
+        let hash = match image_hash(&value, opts.algorithm) {
+            Ok(hash) => hash,
+            Err(e) => {
+                let image = value.get("@id").and_then(Value::as_str);
+                warn_input_error(
+                    flags,
+                    "failed to hash image",
+                    &e,
+                    InputContext { image, ..input },
+                );
+                continue;
+            },
+        };
+
+        let nearest = clusters
+            .iter()
+            .enumerate()
+            .map(|(index, cluster)| (index, hamming_distance(hash, cluster.hash)))
+            .filter(|&(_, distance)| distance <= opts.threshold)
+            .min_by_key(|&(_, distance)| distance);
+
+        let id = value.get("@id").and_then(Value::as_str).map(String::from);
+
+        #[cfg(feature = "tracing")]
+        asimov_module::tracing::debug!(
+            target: "asimov_image_module::dedup",
+            id = ?id,
+            hash = %format_hash(hash),
+            nearest = ?nearest,
+            "hashed image"
+        );
+
+        jsonld::set_context(&mut value, opts.jsonld.context.as_ref());
+        let output = match (opts.mode, nearest) {
+            (Mode::Unique, Some(_)) => continue,
+            (Mode::Unique, None) => {
+                clusters.push(Cluster { hash, id });
+                value.to_string()
+            },
+            (Mode::Annotate, nearest) => {
+                let (index, distance) = nearest.unwrap_or_else(|| {
+                    clusters.push(Cluster { hash, id });
+                    (clusters.len() - 1, 0)
+                });
+                if let Some(obj) = value.as_object_mut() {
+                    obj.entry(opts.algorithm.property())
+                        .or_insert_with(|| format_hash(hash).into());
+                    obj.insert("cluster".into(), index.into());
+                    obj.insert("hammingDistance".into(), distance.into());
+                    if nearest.is_some()
+                        && let Some(rep) = &clusters[index].id
+                    {
+                        obj.insert("duplicateOf".into(), rep.clone().into());
+                    }
+                }
+                serde_json::to_string(&value).map_err(|e| Error::JsonLd(e.to_string()))?
+            },
+        };
+
+        writeln!(stdout, "{output}")
+            .and_then(|_| stdout.flush())
+            .map_err(|e| Error::Io {
+                context: "writing to stdout",
+                source: e,
+            })?;
+    }
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::dedup",
+        clusters = clusters.len(),
+        "dedup exiting"
+    );
+
+    Ok(())
+}
+
+/// Reuses a precomputed hash property (as emitted by `asimov-image-reader
+/// --perceptual-hash`) when present, otherwise hashes the pixel payload.
+fn image_hash(value: &Value, algorithm: PerceptualHash) -> CoreResult<u64> {
+    if let Some(hash) = value
+        .get(algorithm.property())
+        .and_then(Value::as_str)
+        .and_then(parse_hash)
+    {
+        return Ok(hash);
+    }
+
+    let img = KnowImage::deserialize(value).map_err(|e| Error::Parse {
+        line: None,
+        message: e.to_string(),
+    })?;
+    let img = pixels::to_dynamic_image(&img)?;
+    Ok(algorithm.compute(&img))
+}
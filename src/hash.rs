@@ -0,0 +1,257 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Content and perceptual hashing of images.
+
+use image::{DynamicImage, imageops::FilterType};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// A perceptual hash algorithm.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PerceptualHash {
+    /// Average hash: 8x8 grayscale thumbnail compared to its mean.
+    Ahash,
+    /// Difference hash: horizontal gradients of a 9x8 grayscale thumbnail.
+    Dhash,
+    /// DCT hash: low frequencies of a 32x32 grayscale thumbnail.
+    #[default]
+    Phash,
+}
+
+impl PerceptualHash {
+    /// Returns the JSON-LD property name used for this hash.
+    pub fn property(self) -> &'static str {
+        match self {
+            Self::Ahash => "ahash",
+            Self::Dhash => "dhash",
+            Self::Phash => "phash",
+        }
+    }
+
+    /// Computes this hash of the given image.
+    pub fn compute(self, img: &DynamicImage) -> u64 {
+        match self {
+            Self::Ahash => ahash(img),
+            Self::Dhash => dhash(img),
+            Self::Phash => phash(img),
+        }
+    }
+}
+
+/// All perceptual hashes of a decoded image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PerceptualHashes {
+    pub ahash: u64,
+    pub dhash: u64,
+    pub phash: u64,
+}
+
+impl PerceptualHashes {
+    pub fn compute(img: &DynamicImage) -> Self {
+        Self {
+            ahash: ahash(img),
+            dhash: dhash(img),
+            phash: phash(img),
+        }
+    }
+
+    /// Adds the `ahash`, `dhash` and `phash` properties to a JSON-LD object.
+    pub fn extend_jsonld(&self, obj: &mut Map<String, Value>) {
+        obj.insert("ahash".into(), format_hash(self.ahash).into());
+        obj.insert("dhash".into(), format_hash(self.dhash).into());
+        obj.insert("phash".into(), format_hash(self.phash).into());
+    }
+}
+
+/// Returns the lowercase hex SHA-256 digest of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Formats a 64-bit hash as 16 lowercase hex digits.
+pub fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// Parses a hash previously produced by [`format_hash`].
+pub fn parse_hash(s: &str) -> Option<u64> {
+    u64::from_str_radix(s, 16).ok()
+}
+
+/// Number of differing bits between two hashes.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Average hash (aHash).
+pub fn ahash(img: &DynamicImage) -> u64 {
+    let pixels = grayscale(img, 8, 8);
+    let mean = pixels.iter().sum::<f32>() / pixels.len() as f32;
+    to_bits(pixels.iter().map(|&p| p > mean))
+}
+
+/// Difference hash (dHash).
+pub fn dhash(img: &DynamicImage) -> u64 {
+    let pixels = grayscale(img, 9, 8);
+    to_bits(
+        pixels
+            .chunks_exact(9)
+            .flat_map(|row| row.windows(2).map(|w| w[0] > w[1])),
+    )
+}
+
+/// DCT-based perceptual hash (pHash).
+pub fn phash(img: &DynamicImage) -> u64 {
+    const N: usize = 32;
+    const K: usize = 8;
+
+    let pixels = grayscale(img, N as u32, N as u32);
+    let coeffs = dct_2d(&pixels, N);
+
+    let low: Vec<f32> = (0..K)
+        .flat_map(|v| (0..K).map(move |u| (u, v)))
+        .map(|(u, v)| coeffs[v * N + u])
+        .collect();
+
+    // The DC term dominates and carries no structure, so leave it out of the median:
+    let mut sorted = low[1..].to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    to_bits(low.iter().map(|&c| c > median))
+}
+
+fn grayscale(img: &DynamicImage, width: u32, height: u32) -> Vec<f32> {
+    img.resize_exact(width, height, FilterType::Triangle)
+        .to_luma8()
+        .into_raw()
+        .into_iter()
+        .map(f32::from)
+        .collect()
+}
+
+fn to_bits(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.take(64)
+        .fold(0u64, |acc, bit| (acc << 1) | u64::from(bit))
+}
+
+fn dct_2d(pixels: &[f32], n: usize) -> Vec<f32> {
+    use std::f32::consts::PI;
+
+    let table: Vec<f32> = (0..n)
+        .flat_map(|k| {
+            (0..n).map(move |x| ((2 * x + 1) as f32 * k as f32 * PI / (2 * n) as f32).cos())
+        })
+        .collect();
+
+    let mut rows = vec![0f32; n * n];
+    for y in 0..n {
+        for u in 0..n {
+            rows[y * n + u] = (0..n).map(|x| pixels[y * n + x] * table[u * n + x]).sum();
+        }
+    }
+
+    let mut out = vec![0f32; n * n];
+    for v in 0..n {
+        for u in 0..n {
+            out[v * n + u] = (0..n).map(|y| rows[y * n + u] * table[v * n + y]).sum();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gray(width: u32, height: u32, f: impl Fn(u32, u32) -> u8) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| Luma([f(x, y)])))
+    }
+
+    /// Black left half, white right half.
+    fn halves(size: u32) -> DynamicImage {
+        gray(size, size, |x, _| if x < size / 2 { 0 } else { 255 })
+    }
+
+    #[test]
+    fn sha256_of_known_input() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn formats_and_parses_hashes() {
+        assert_eq!(format_hash(0xabc), "0000000000000abc");
+        assert_eq!(format_hash(u64::MAX), "ffffffffffffffff");
+        for hash in [0, 1, 0x8000_0000_0000_0000, 0x0123_4567_89ab_cdef, u64::MAX] {
+            assert_eq!(parse_hash(&format_hash(hash)), Some(hash));
+        }
+        assert_eq!(parse_hash("ABCDEF"), Some(0xabcdef));
+        assert_eq!(parse_hash("xyz"), None);
+        assert_eq!(parse_hash("10000000000000000"), None);
+    }
+
+    #[test]
+    fn hamming_distance_counts_differing_bits() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(0xf0, 0x0f), 8);
+    }
+
+    #[test]
+    fn ahash_of_split_image() {
+        // Each row of the thumbnail is 0000_1111:
+        assert_eq!(ahash(&halves(8)), 0x0f0f_0f0f_0f0f_0f0f);
+        assert_eq!(ahash(&halves(64)), 0x0f0f_0f0f_0f0f_0f0f);
+    }
+
+    #[test]
+    fn dhash_of_horizontal_gradients() {
+        let falling = gray(9, 8, |x, _| 255 - 28 * x as u8);
+        assert_eq!(dhash(&falling), u64::MAX);
+        let rising = gray(9, 8, |x, _| 28 * x as u8);
+        assert_eq!(dhash(&rising), 0);
+    }
+
+    /// Deterministic noise, `scale` pixels per noise sample.
+    fn noise(size: u32, scale: u32) -> DynamicImage {
+        gray(size, size, |x, y| {
+            let n = (x / scale) * 31 + (y / scale) * 17;
+            (n.wrapping_mul(2_654_435_761) >> 24) as u8
+        })
+    }
+
+    #[test]
+    fn phash_of_noise() {
+        let hash = phash(&noise(32, 1));
+        assert_eq!(hash, 0xa00a_e1f4_597c_96af);
+        // Scaling barely changes the hash:
+        assert!(hamming_distance(phash(&noise(256, 8)), hash) <= 4);
+        // Inverting the image flips nearly every bit:
+        let mut inverted = noise(32, 1);
+        inverted.invert();
+        assert!(hamming_distance(phash(&inverted), hash) >= 56);
+    }
+
+    #[test]
+    fn computes_every_hash() {
+        let img = halves(32);
+        let hashes = PerceptualHashes::compute(&img);
+        assert_eq!(hashes.ahash, PerceptualHash::Ahash.compute(&img));
+        assert_eq!(hashes.dhash, PerceptualHash::Dhash.compute(&img));
+        assert_eq!(hashes.phash, PerceptualHash::Phash.compute(&img));
+
+        let mut obj = Map::new();
+        hashes.extend_jsonld(&mut obj);
+        assert_eq!(obj["ahash"], "0f0f0f0f0f0f0f0f");
+        assert_eq!(obj.len(), 3);
+    }
+}
@@ -0,0 +1,340 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Header-level facts about encoded images, read without decoding the pixels.
+
+use crate::core::{Error, Result};
+use image::{ImageDecoder, ImageFormat, ImageReader};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::Cursor;
+
+/// What an image file's headers and metadata say about it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageInfo {
+    /// Short format name, such as `png` or `jpeg`.
+    pub format: String,
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Color type as stored in the file, such as `rgb8` or `la16`.
+    pub color_type: String,
+    pub channels: u8,
+    /// Bits per channel.
+    pub bit_depth: u16,
+    /// Number of frames; above 1 only for animated GIF, PNG and WebP.
+    pub frames: usize,
+    /// Size of the encoded file in bytes.
+    pub file_size: u64,
+    pub has_icc_profile: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exif: Option<ExifSummary>,
+}
+
+/// The commonly wanted EXIF fields, formatted for display.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExifSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// When the picture was taken, as `YYYY-MM-DD HH:MM:SS`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_time: Option<String>,
+    /// EXIF orientation, `1` (upright) to `8`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub f_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iso: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focal_length: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+    /// Whether the picture is geotagged.
+    pub gps: bool,
+}
+
+impl ImageInfo {
+    /// Reads the headers of the encoded image in `data`, read from `path` (a
+    /// file path or URL, for errors). No pixel data is decoded.
+    pub fn probe(path: &str, data: &[u8]) -> Result<Self> {
+        let reader = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .map_err(|e| Error::Io {
+                context: "reading image header",
+                source: e,
+            })?;
+        let format = reader.format().ok_or_else(|| Error::UnsupportedFormat {
+            path: path.into(),
+            reason: "the image format could not be determined".into(),
+        })?;
+
+        let mut decoder = reader
+            .into_decoder()
+            .map_err(|e| Error::decoding(path, e))?;
+        let (width, height) = decoder.dimensions();
+        let color = decoder.original_color_type();
+        let channels = color.channel_count();
+        let has_icc_profile = decoder.icc_profile().ok().flatten().is_some();
+        let exif = decoder
+            .exif_metadata()
+            .ok()
+            .flatten()
+            .and_then(ExifSummary::parse);
+
+        Ok(Self {
+            format: format!("{format:?}").to_lowercase(),
+            mime_type: format.to_mime_type().into(),
+            width,
+            height,
+            color_type: format!("{color:?}").to_lowercase(),
+            channels,
+            bit_depth: color.bits_per_pixel() / u16::from(channels.max(1)),
+            frames: count_frames(format, data),
+            file_size: data.len() as u64,
+            has_icc_profile,
+            exif,
+        })
+    }
+
+    /// Adds the facts to a JSON-LD object.
+    pub fn extend_jsonld(&self, obj: &mut Map<String, Value>) {
+        if let Ok(Value::Object(props)) = serde_json::to_value(self) {
+            obj.extend(props);
+        }
+    }
+}
+
+/// Counts the frames of an animated GIF, PNG or WebP file by walking its
+/// block or chunk structure, without decoding them; 1 for other formats.
+/// Truncated or malformed structures count the frames found so far.
+fn count_frames(format: ImageFormat, data: &[u8]) -> usize {
+    let frames = match format {
+        ImageFormat::Gif => gif_frames(data),
+        ImageFormat::Png => apng_frames(data),
+        ImageFormat::WebP => webp_frames(data),
+        _ => None,
+    };
+    frames.filter(|&n| n > 0).unwrap_or(1)
+}
+
+/// Counts the image descriptors of a GIF file.
+fn gif_frames(data: &[u8]) -> Option<usize> {
+    /// Skips a sequence of data sub-blocks, returning the offset after it.
+    fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+        loop {
+            let len = usize::from(*data.get(pos)?);
+            pos += 1 + len;
+            if len == 0 {
+                return Some(pos);
+            }
+        }
+    }
+    /// Size of the color table announced by a packed field.
+    fn color_table(packed: u8) -> usize {
+        if packed & 0x80 != 0 {
+            3 << ((packed & 0x07) + 1)
+        } else {
+            0
+        }
+    }
+
+    // Header and logical screen descriptor:
+    let mut pos = 13 + color_table(*data.get(10)?);
+    let mut frames = 0;
+    loop {
+        let next = match data.get(pos) {
+            // Image descriptor, then an optional local color table, the LZW
+            // minimum code size and the image data:
+            Some(0x2C) => {
+                frames += 1;
+                data.get(pos + 9)
+                    .and_then(|&packed| skip_sub_blocks(data, pos + 11 + color_table(packed)))
+            },
+            // Extension: label, then sub-blocks:
+            Some(0x21) => skip_sub_blocks(data, pos + 2),
+            // Trailer or an unknown block:
+            _ => None,
+        };
+        // Stop at the trailer, an unknown block or truncated data:
+        match next {
+            Some(next) => pos = next,
+            None => return Some(frames),
+        }
+    }
+}
+
+/// Reads the frame count of an APNG's `acTL` chunk; `None` for still PNGs.
+fn apng_frames(data: &[u8]) -> Option<usize> {
+    // Chunks follow the 8-byte signature; `acTL` must come before `IDAT`:
+    let mut pos = 8;
+    loop {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        match data.get(pos + 4..pos + 8)? {
+            b"acTL" => {
+                let frames = data.get(pos + 8..pos + 12)?;
+                return Some(u32::from_be_bytes(frames.try_into().ok()?) as usize);
+            },
+            b"IDAT" => return None,
+            _ => pos += 12 + len,
+        }
+    }
+}
+
+/// Counts the `ANMF` chunks of an animated WebP; `None` for still images.
+fn webp_frames(data: &[u8]) -> Option<usize> {
+    // Chunks follow the 12-byte RIFF header:
+    let mut pos = 12;
+    let mut frames = 0;
+    while let Some(fourcc) = data.get(pos..pos + 4) {
+        let Some(size) = data.get(pos + 4..pos + 8) else {
+            break;
+        };
+        let size = u32::from_le_bytes(size.try_into().ok()?) as usize;
+        if fourcc == b"ANMF" {
+            frames += 1;
+        }
+        // Chunks are padded to an even size:
+        pos += 8 + size + (size & 1);
+    }
+    Some(frames)
+}
+
+impl ExifSummary {
+    /// Summarizes raw EXIF data as found in the image; `None` if it is malformed.
+    pub fn parse(raw: Vec<u8>) -> Option<Self> {
+        use exif::{In, Tag};
+
+        let exif = exif::Reader::new().read_raw(raw).ok()?;
+        let text = |tag| {
+            exif.get_field(tag, In::PRIMARY).map(|field| {
+                field
+                    .display_value()
+                    .with_unit(&exif)
+                    .to_string()
+                    .trim_matches('"')
+                    .to_string()
+            })
+        };
+
+        Some(Self {
+            make: text(Tag::Make),
+            model: text(Tag::Model),
+            date_time: text(Tag::DateTimeOriginal).or_else(|| text(Tag::DateTime)),
+            orientation: exif
+                .get_field(Tag::Orientation, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0)),
+            exposure_time: text(Tag::ExposureTime),
+            f_number: text(Tag::FNumber),
+            iso: text(Tag::PhotographicSensitivity),
+            focal_length: text(Tag::FocalLength),
+            software: text(Tag::Software),
+            gps: exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba, RgbaImage};
+
+    fn gif(frames: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut data);
+            for i in 0..frames {
+                let pixels = RgbaImage::from_pixel(3, 2, Rgba([i as u8 * 40, 0, 0, 255]));
+                let delay = Delay::from_numer_denom_ms(100, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(pixels, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        data
+    }
+
+    /// A PNG signature followed by `chunks`, with dummy CRCs.
+    fn png(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, body) in chunks {
+            data.extend((body.len() as u32).to_be_bytes());
+            data.extend(*kind);
+            data.extend(*body);
+            data.extend([0; 4]);
+        }
+        data
+    }
+
+    /// A RIFF WebP header followed by `chunks`.
+    fn webp(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        for (kind, body) in chunks {
+            data.extend(*kind);
+            data.extend((body.len() as u32).to_le_bytes());
+            data.extend(*body);
+            if body.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn counts_gif_frames() {
+        assert_eq!(count_frames(ImageFormat::Gif, &gif(1)), 1);
+        assert_eq!(count_frames(ImageFormat::Gif, &gif(3)), 3);
+    }
+
+    #[test]
+    fn counts_truncated_gif_frames_found() {
+        let data = gif(3);
+        assert_eq!(count_frames(ImageFormat::Gif, &data[..data.len() - 1]), 3);
+        // The third image descriptor, after its graphic control extension,
+        // cut short before its packed field:
+        let gce = data
+            .windows(3)
+            .rposition(|w| w == [0x21, 0xF9, 0x04])
+            .unwrap();
+        let third = gce + 8;
+        assert_eq!(data[third], 0x2C);
+        assert_eq!(count_frames(ImageFormat::Gif, &data[..third + 5]), 3);
+        assert_eq!(count_frames(ImageFormat::Gif, &data[..13]), 1);
+    }
+
+    #[test]
+    fn reads_apng_frame_count() {
+        let ihdr: &[u8] = &[0; 13];
+        let actl: &[u8] = &[0, 0, 0, 5, 0, 0, 0, 0];
+        let apng = png(&[(b"IHDR", ihdr), (b"acTL", actl), (b"IDAT", &[])]);
+        assert_eq!(count_frames(ImageFormat::Png, &apng), 5);
+        // An `acTL` after `IDAT` is not an animation control chunk:
+        let still = png(&[(b"IHDR", ihdr), (b"IDAT", &[]), (b"acTL", actl)]);
+        assert_eq!(count_frames(ImageFormat::Png, &still), 1);
+    }
+
+    #[test]
+    fn counts_webp_animation_frames() {
+        let animated = webp(&[
+            (b"VP8X", &[0; 10]),
+            (b"ANIM", &[0; 6]),
+            (b"ANMF", &[0; 17]),
+            (b"ANMF", &[0; 16]),
+        ]);
+        assert_eq!(count_frames(ImageFormat::WebP, &animated), 2);
+        let still = webp(&[(b"VP8 ", &[0; 11])]);
+        assert_eq!(count_frames(ImageFormat::WebP, &still), 1);
+    }
+
+    #[test]
+    fn probes_animated_gif() {
+        let info = ImageInfo::probe("file:/a.gif", &gif(3)).unwrap();
+        assert_eq!((info.width, info.height, info.frames), (3, 2, 3));
+    }
+}
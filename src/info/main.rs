@@ -0,0 +1,267 @@
+// This is free and unencumbered software released into the public domain.
+
+#[cfg(not(feature = "std"))]
+compile_error!("asimov-image-info requires the 'std' feature");
+
+use asimov_image_module::{
+    codec::Input,
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, init_error_output,
+        warn_input_error,
+    },
+    info::ImageInfo,
+    jsonld::{self, ContextOptions},
+    tally::{FailureOptions, Tally},
+};
+use asimov_module::SysexitsError::{self, *};
+use clap::{Parser, ValueEnum};
+use clientele::StandardOptions;
+use serde_json::{Map, Value, json};
+use std::error::Error as StdError;
+use std::io::{self, Write};
+
+/// asimov-image-info
+#[derive(Debug, Parser)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    jsonld: ContextOptions,
+
+    #[clap(flatten)]
+    failures: FailureOptions,
+
+    /// Image files to describe ("-" for stdin)
+    #[arg(value_name = "FILES", required = true)]
+    files: Vec<String>,
+
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Jsonld)]
+    output: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// One line of JSON-LD per file
+    Jsonld,
+    /// An aligned table with one row per file
+    Table,
+}
+
+pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
+    // Load environment variables from `.env`:
+    asimov_module::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
+    let exit_code = match run_info(&options) {
+        Ok(()) => EX_OK,
+        Err(err) => handle_error(&err, &options.flags),
+    };
+
+    Ok(exit_code)
+}
+
+fn run_info(opts: &Options) -> CoreResult<()> {
+    let flags = &opts.flags;
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::info",
+        files = opts.files.len(),
+        output = ?opts.output,
+        "starting info"
+    );
+
+    let tally = Tally::new(&opts.failures);
+    let mut rows = Vec::new();
+    let result = probe_files(opts, &tally, &mut rows);
+    if opts.output == OutputFormat::Table {
+        print_table(&rows)?;
+    }
+    tally.report(flags, "probed");
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::info",
+        "info exiting"
+    );
+
+    result
+}
+
+/// Describes every file, printing JSON-LD as it goes or collecting table
+/// rows. Fails with the last error if no file could be described.
+fn probe_files(
+    opts: &Options,
+    tally: &Tally,
+    rows: &mut Vec<(String, ImageInfo)>,
+) -> CoreResult<()> {
+    let mut stdout = io::stdout();
+    let mut last_error = None;
+
+    for file in &opts.files {
+        let probed = Input::read(Some(file)).and_then(|input| {
+            tally.record_read();
+            let id = input.file_url().unwrap_or_else(|| "_:stdin".into());
+            let info = ImageInfo::probe(&id, &input.data)?;
+            tally.record_parsed();
+            Ok((id, info))
+        });
+        let (id, info) = match probed {
+            Ok(probed) => probed,
+            Err(e) => {
+                let input = InputContext {
+                    line: None,
+                    image: Some(file),
+                };
+                warn_input_error(&opts.flags, "failed to describe image", &e, input);
+                if tally.count_failure() {
+                    return Err(e);
+                }
+                last_error = Some(e);
+                continue;
+            },
+        };
+
+        #[cfg(feature = "tracing")]
+        asimov_module::tracing::debug!(
+            target: "asimov_image_module::info",
+            id = %id,
+            info = ?info,
+            "probed image"
+        );
+
+        match opts.output {
+            OutputFormat::Jsonld => {
+                let mut value = to_jsonld(&id, &info);
+                jsonld::set_context(&mut value, opts.jsonld.context.as_ref());
+                writeln!(stdout, "{value}")
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| Error::Io {
+                        context: "writing to stdout",
+                        source: e,
+                    })?;
+            },
+            OutputFormat::Table => rows.push((id, info)),
+        }
+        tally.record_processed();
+    }
+
+    match last_error {
+        Some(err) if tally.parsed() == 0 => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn to_jsonld(id: &str, info: &ImageInfo) -> Value {
+    let mut obj = Map::new();
+    obj.insert("@type".into(), json!("Image"));
+    obj.insert("@id".into(), json!(id));
+    info.extend_jsonld(&mut obj);
+    if !id.starts_with("_:") {
+        obj.insert("source".into(), json!(id));
+    }
+    Value::Object(obj)
+}
+
+fn print_table(rows: &[(String, ImageInfo)]) -> CoreResult<()> {
+    let header = [
+        "FILE", "FORMAT", "SIZE", "COLOR", "DEPTH", "FRAMES", "BYTES", "ICC", "EXIF",
+    ];
+    let cells: Vec<[String; 9]> = rows
+        .iter()
+        .map(|(id, info)| {
+            [
+                id.strip_prefix("file:").unwrap_or(id).to_string(),
+                info.format.clone(),
+                format!("{}x{}", info.width, info.height),
+                info.color_type.clone(),
+                info.bit_depth.to_string(),
+                info.frames.to_string(),
+                info.file_size.to_string(),
+                if info.has_icc_profile { "yes" } else { "no" }.into(),
+                info.exif.as_ref().map_or("-".into(), exif_summary),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let header = header.map(String::from);
+    for row in std::iter::once(&header).chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(out.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(|e| Error::Io {
+            context: "writing to stdout",
+            source: e,
+        })
+}
+
+/// Camera, date and GPS presence, as far as known.
+fn exif_summary(exif: &asimov_image_module::info::ExifSummary) -> String {
+    let camera = [exif.make.as_deref(), exif.model.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let parts: Vec<&str> = [
+        Some(camera.as_str()).filter(|c| !c.is_empty()),
+        exif.date_time.as_deref(),
+        exif.gps.then_some("GPS"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if parts.is_empty() {
+        "yes".into()
+    } else {
+        parts.join(", ")
+    }
+}
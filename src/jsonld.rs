@@ -0,0 +1,474 @@
+// This is free and unencumbered software released into the public domain.
+
+//! JSON-LD normalization of image nodes, and the `@context` of emitted ones.
+
+use crate::core::{Error, Result};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Vocabularies whose image terms are understood on input.
+const VOCABULARIES: &[&str] = &[
+    "https://know.dev/",
+    "http://know.dev/",
+    "https://schema.org/",
+    "http://schema.org/",
+];
+
+/// Types accepted as an image, in any of the known vocabularies.
+const IMAGE_TYPES: &[&str] = &["Image", "ImageObject"];
+
+/// Properties of `know::classes::Image`, besides `@id`.
+const IMAGE_TERMS: &[&str] = &["width", "height", "data", "source"];
+
+/// Command-line options for the `@context` of emitted JSON-LD.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct ContextOptions {
+    /// `@context` added to every emitted image: an IRI, or an inline JSON object or array
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_name = "CONTEXT", value_parser = parse_context)
+    )]
+    pub context: Option<Value>,
+}
+
+#[cfg(feature = "cli")]
+fn parse_context(s: &str) -> std::result::Result<Value, String> {
+    if s.starts_with(['{', '[']) {
+        serde_json::from_str(s).map_err(|e| format!("Invalid context '{s}': {e}"))
+    } else if s.contains(':') {
+        Ok(Value::String(s.into()))
+    } else {
+        Err(format!(
+            "Invalid context '{s}'. Use an IRI or a JSON object (e.g., https://know.dev/)"
+        ))
+    }
+}
+
+/// Sets `context` as the node's `@context`, placed before its other
+/// properties. Does nothing for `None`.
+pub fn set_context(value: &mut Value, context: Option<&Value>) {
+    let (Some(context), Value::Object(obj)) = (context, value) else {
+        return;
+    };
+    let mut node = Map::with_capacity(obj.len() + 1);
+    node.insert("@context".into(), context.clone());
+    node.extend(
+        std::mem::take(obj)
+            .into_iter()
+            .filter(|(k, _)| k != "@context"),
+    );
+    *obj = node;
+}
+
+/// Formats a node as indented JSON for human inspection, with the pixel
+/// `data` summarized as its first bytes and decoded size.
+#[cfg(feature = "pretty")]
+pub fn to_pretty_string(value: &Value) -> String {
+    /// Base64 characters of the pixel data shown before eliding the rest.
+    const SHOWN: usize = 16;
+
+    let mut value = value.clone();
+    if let Some(Value::String(data)) = value.get_mut("data") {
+        let (header, payload) = data.split_at(data.find(',').map_or(0, |i| i + 1));
+        // Payloads that are not base64 may not split at `SHOWN` bytes:
+        if payload.len() > SHOWN
+            && let Some(shown) = payload.get(..SHOWN)
+        {
+            let padding = payload.bytes().rev().take_while(|&b| b == b'=').count();
+            let size = (payload.len() / 4 * 3).saturating_sub(padding);
+            *data = format!("{header}{shown}… ({size} bytes)");
+        }
+    }
+    serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Rewrites an image node into the compacted form `know::classes::Image`
+/// deserializes: its `@context` is resolved, compact and absolute IRIs of
+/// known image terms are replaced by the plain terms, expanded values such
+/// as `[{"@value": 64}]` are unwrapped, and any image `@type` becomes `Image`.
+///
+/// The input `@context` is dropped, so other properties are renamed to the
+/// absolute IRIs it maps them to, keeping their meaning; plain names and
+/// [KNOW](https://know.dev/) terms stay plain names. Fails with
+/// `Error::Parse` if the node has a `@type` that is not an image.
+pub fn normalize_image(value: &mut Value) -> Result<()> {
+    let Value::Object(obj) = value else {
+        return Err(Error::Parse {
+            line: None,
+            message: format!("expected an image object, found {}", describe(value)),
+        });
+    };
+    let context = Context::from_value(obj.get("@context"));
+
+    let mut node = Map::with_capacity(obj.len());
+    for (key, value) in std::mem::take(obj) {
+        let key = match context.expand(&key) {
+            iri if iri.starts_with('@') => iri,
+            iri => compact(&iri)
+                .map(String::from)
+                .unwrap_or_else(|| compact_know(iri)),
+        };
+        match key.as_str() {
+            "@context" => {},
+            "@type" => {
+                node.insert(key, Value::String(image_type(&context, &value)?.into()));
+            },
+            "@id" => {
+                node.insert(key, unwrap_value(value));
+            },
+            term if IMAGE_TERMS.contains(&term) => {
+                let value = match (term, unwrap_value(value)) {
+                    ("width" | "height", Value::String(s)) => s
+                        .parse::<u64>()
+                        .map(Value::from)
+                        .unwrap_or(Value::String(s)),
+                    (_, value) => value,
+                };
+                node.insert(key, value);
+            },
+            _ => {
+                node.insert(key, value);
+            },
+        }
+    }
+
+    *obj = node;
+    Ok(())
+}
+
+/// Returns `Image` if any of the types in `value` is a known image type.
+fn image_type(context: &Context, value: &Value) -> Result<&'static str> {
+    let types: Vec<&str> = match value {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let is_image = types.iter().any(|t| {
+        compact(&context.expand(t))
+            .or_else(|| compact(t))
+            .is_some_and(|t| IMAGE_TYPES.contains(&t))
+    });
+    if is_image {
+        Ok("Image")
+    } else {
+        Err(Error::Parse {
+            line: None,
+            message: format!("expected an Image, found @type {value}"),
+        })
+    }
+}
+
+/// Unwraps a single-element array, a value object or a node reference.
+fn unwrap_value(value: Value) -> Value {
+    match value {
+        Value::Array(mut values) if values.len() == 1 => unwrap_value(values.remove(0)),
+        Value::Object(mut obj) if obj.contains_key("@value") => {
+            obj.remove("@value").unwrap_or_default()
+        },
+        Value::Object(mut obj) if obj.len() == 1 && obj.contains_key("@id") => {
+            obj.remove("@id").unwrap_or_default()
+        },
+        value => value,
+    }
+}
+
+/// Maps an IRI in a known vocabulary to its image term or type.
+fn compact(iri: &str) -> Option<&'static str> {
+    let local = VOCABULARIES
+        .iter()
+        .find_map(|vocab| iri.strip_prefix(vocab))
+        .unwrap_or(iri);
+    IMAGE_TERMS
+        .iter()
+        .chain(IMAGE_TYPES)
+        .find(|&&term| term == local)
+        .copied()
+}
+
+/// Maps an IRI in the KNOW vocabulary to its plain name, which is how
+/// properties without a context are read.
+fn compact_know(iri: String) -> String {
+    let local = ["https://know.dev/", "http://know.dev/"]
+        .iter()
+        .find_map(|vocab| iri.strip_prefix(vocab))
+        .filter(|local| !local.is_empty() && !local.contains([':', '/', '#']));
+    match local {
+        Some(local) => local.into(),
+        None => iri,
+    }
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// The parts of a `@context` needed to expand terms: its `@vocab` and its
+/// term and prefix definitions. Remote contexts are not fetched; an IRI is
+/// taken as the vocabulary its terms belong to.
+#[derive(Debug, Default)]
+struct Context {
+    vocab: Option<String>,
+    terms: HashMap<String, String>,
+}
+
+impl Context {
+    fn from_value(value: Option<&Value>) -> Self {
+        let mut context = Self::default();
+        if let Some(value) = value {
+            context.merge(value);
+        }
+        context
+    }
+
+    fn merge(&mut self, value: &Value) {
+        match value {
+            Value::String(iri) => self.vocab = Some(iri.clone()),
+            Value::Array(values) => values.iter().for_each(|value| self.merge(value)),
+            Value::Object(defs) => {
+                for (term, def) in defs {
+                    let iri = match def {
+                        Value::String(iri) => iri.as_str(),
+                        Value::Object(def) => match def.get("@id").and_then(Value::as_str) {
+                            Some(iri) => iri,
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    if term == "@vocab" {
+                        self.vocab = Some(iri.into());
+                    } else {
+                        self.terms.insert(term.clone(), iri.into());
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Expands a term, compact IRI or keyword alias to an IRI or keyword.
+    fn expand(&self, term: &str) -> String {
+        if term.starts_with('@') {
+            return term.into();
+        }
+        if let Some(iri) = self.terms.get(term) {
+            return self.expand_prefix(iri);
+        }
+        if term.contains(':') {
+            return self.expand_prefix(term);
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{vocab}{term}"),
+            None => term.into(),
+        }
+    }
+
+    fn expand_prefix(&self, iri: &str) -> String {
+        match iri.split_once(':') {
+            Some((prefix, suffix)) if !suffix.starts_with("//") => match self.terms.get(prefix) {
+                Some(base) => format!("{base}{suffix}"),
+                None => iri.into(),
+            },
+            _ => iri.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn normalized(mut value: Value) -> Value {
+        normalize_image(&mut value).unwrap();
+        value
+    }
+
+    #[test]
+    fn keeps_a_compacted_image() {
+        let image = json!({"@type": "Image", "@id": "_:a", "width": 2, "height": 1, "data": "x"});
+        assert_eq!(normalized(image.clone()), image);
+    }
+
+    #[test]
+    fn resolves_compact_iris() {
+        let image = json!({
+            "@context": {"k": "https://know.dev/"},
+            "@type": "k:Image",
+            "k:width": 64,
+            "k:height": "48",
+        });
+        assert_eq!(
+            normalized(image),
+            json!({"@type": "Image", "width": 64, "height": 48})
+        );
+    }
+
+    #[test]
+    fn resolves_the_vocabulary_and_aliases() {
+        let image = json!({
+            "@context": {"@vocab": "https://know.dev/", "id": "@id", "w": "https://know.dev/width"},
+            "id": "_:a",
+            "@type": "Image",
+            "w": 3,
+            "height": 4,
+        });
+        assert_eq!(
+            normalized(image),
+            json!({"@id": "_:a", "@type": "Image", "width": 3, "height": 4})
+        );
+    }
+
+    #[test]
+    fn unwraps_expanded_values() {
+        let image = json!({
+            "@id": "_:a",
+            "@type": ["https://know.dev/Image"],
+            "https://know.dev/width": [{"@value": 64}],
+            "https://know.dev/height": [{"@value": "48"}],
+            "https://know.dev/source": [{"@id": "file:/a.png"}],
+        });
+        assert_eq!(
+            normalized(image),
+            json!({"@id": "_:a", "@type": "Image", "width": 64, "height": 48, "source": "file:/a.png"})
+        );
+    }
+
+    #[test]
+    fn accepts_schema_org_image_objects() {
+        let image = json!({
+            "@context": "https://schema.org/",
+            "@type": "ImageObject",
+            "width": 10,
+            "height": 20,
+        });
+        assert_eq!(
+            normalized(image),
+            json!({"@type": "Image", "width": 10, "height": 20})
+        );
+    }
+
+    #[test]
+    fn expands_other_properties_defined_by_the_context() {
+        let image = json!({
+            "@context": {
+                "@vocab": "https://schema.org/",
+                "ex": "https://example.org/",
+                "k": "https://know.dev/",
+            },
+            "@type": "ImageObject",
+            "ex:camera": "X100",
+            "caption": "Beach",
+            "k:sharpness": 12.5,
+        });
+        assert_eq!(
+            normalized(image),
+            json!({
+                "@type": "Image",
+                "https://example.org/camera": "X100",
+                "https://schema.org/caption": "Beach",
+                "sharpness": 12.5,
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_plain_names_without_a_context() {
+        let image = json!({"@type": "Image", "sharpness": 3.0, "ahash": "00ff"});
+        assert_eq!(normalized(image.clone()), image);
+    }
+
+    #[test]
+    fn rejects_other_types() {
+        let mut person = json!({"@context": "https://schema.org/", "@type": "Person"});
+        let error = normalize_image(&mut person).unwrap_err();
+        assert!(matches!(&error, Error::Parse { message, .. } if message.contains("Person")));
+
+        let mut array = json!([1, 2]);
+        assert!(matches!(
+            normalize_image(&mut array),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn sets_the_context_first() {
+        let mut image = json!({"@type": "Image", "@context": "https://old/", "width": 1});
+        set_context(&mut image, Some(&json!("https://know.dev/")));
+        assert_eq!(
+            serde_json::to_string(&image).unwrap(),
+            r#"{"@context":"https://know.dev/","@type":"Image","width":1}"#
+        );
+
+        let mut unchanged = json!({"@type": "Image"});
+        set_context(&mut unchanged, None);
+        assert_eq!(unchanged, json!({"@type": "Image"}));
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn parses_context_options() {
+        assert_eq!(
+            parse_context("https://know.dev/"),
+            Ok(json!("https://know.dev/"))
+        );
+        assert_eq!(
+            parse_context(r#"{"@vocab": "https://know.dev/"}"#),
+            Ok(json!({"@vocab": "https://know.dev/"}))
+        );
+        assert!(parse_context("know").is_err());
+    }
+
+    #[cfg(feature = "pretty")]
+    fn pretty_data(bytes: &[u8]) -> Value {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+        let data = format!("data:image/rgb;base64,{}", STANDARD.encode(bytes));
+        let pretty = to_pretty_string(&json!({"@type": "Image", "data": data}));
+        serde_json::from_str::<Value>(&pretty).unwrap()["data"].clone()
+    }
+
+    #[test]
+    #[cfg(feature = "pretty")]
+    fn pretty_string_elides_the_pixels() {
+        // 24 base64 characters without padding:
+        assert_eq!(
+            pretty_data(&[0; 18]),
+            "data:image/rgb;base64,AAAAAAAAAAAAAAAA… (18 bytes)"
+        );
+        // 28 characters ending in `==` and `=`:
+        assert_eq!(
+            pretty_data(&[0; 19]),
+            "data:image/rgb;base64,AAAAAAAAAAAAAAAA… (19 bytes)"
+        );
+        assert_eq!(
+            pretty_data(&[0; 20]),
+            "data:image/rgb;base64,AAAAAAAAAAAAAAAA… (20 bytes)"
+        );
+        // Up to 16 characters are shown whole:
+        assert_eq!(
+            pretty_data(&[0; 12]),
+            "data:image/rgb;base64,AAAAAAAAAAAAAAAA"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "pretty")]
+    fn pretty_string_keeps_other_properties_and_odd_payloads() {
+        let pretty = to_pretty_string(&json!({"@type": "Image", "width": 2}));
+        assert_eq!(pretty, "{\n  \"@type\": \"Image\",\n  \"width\": 2\n}");
+
+        // The 16th byte falls inside a two-byte character:
+        let data = format!("data:,a{}", "é".repeat(20));
+        let value = json!({"data": data});
+        let pretty: Value = serde_json::from_str(&to_pretty_string(&value)).unwrap();
+        assert_eq!(pretty, value);
+    }
+}
@@ -2,4 +2,16 @@
 
 #![forbid(unsafe_code)]
 
+pub mod codec;
+pub mod compare;
 pub mod core;
+pub mod hash;
+pub mod info;
+pub mod jsonld;
+pub mod pixels;
+pub mod quality;
+pub mod rdf;
+pub mod stats;
+pub mod stream;
+pub mod tally;
+pub mod term;
@@ -0,0 +1,43 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Conversions between `know::classes::Image` payloads and `image` buffers.
+
+use crate::core::{Error, Result};
+use image::{DynamicImage, RgbImage};
+use know::classes::Image as KnowImage;
+
+/// Validates the packed RGB payload of an image and returns its `(width, height)`.
+pub fn dimensions(img: &KnowImage) -> Result<(usize, usize)> {
+    let w = img
+        .width
+        .ok_or_else(|| Error::InvalidDimensions("missing image.width".into()))?;
+    let h = img
+        .height
+        .ok_or_else(|| Error::InvalidDimensions("missing image.height".into()))?;
+
+    let expected = w
+        .checked_mul(h)
+        .and_then(|px| px.checked_mul(3))
+        .ok_or_else(|| Error::InvalidBuffer("width*height*3 overflow".into()))?;
+
+    if img.data.len() != expected {
+        return Err(Error::InvalidBuffer(format!(
+            "byte length {} does not match width*height*3 ({expected})",
+            img.data.len()
+        )));
+    }
+
+    Ok((w, h))
+}
+
+/// Copies the payload of an image into an `RgbImage`.
+pub fn to_rgb_image(img: &KnowImage) -> Result<RgbImage> {
+    let (w, h) = dimensions(img)?;
+    RgbImage::from_raw(w as u32, h as u32, img.data.clone())
+        .ok_or_else(|| Error::InvalidBuffer("failed to construct RgbImage from raw data".into()))
+}
+
+/// Copies the payload of an image into a `DynamicImage`.
+pub fn to_dynamic_image(img: &KnowImage) -> Result<DynamicImage> {
+    to_rgb_image(img).map(DynamicImage::ImageRgb8)
+}
@@ -0,0 +1,221 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Sharpness, exposure and blank-frame scoring of RGB images.
+
+use image::{DynamicImage, GrayImage, RgbImage};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Luma at or above which a pixel counts as clipped highlights.
+const OVEREXPOSED_LUMA: u8 = 250;
+
+/// Luma at or below which a pixel counts as crushed shadows.
+const UNDEREXPOSED_LUMA: u8 = 5;
+
+/// Quality scores of an RGB image.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityScores {
+    /// Variance of the Laplacian of the luma channel; low values mean blur.
+    pub sharpness: f64,
+    /// Mean luma in `0..=255`.
+    pub brightness: f64,
+    /// Standard deviation of luma; near zero for uniform frames.
+    pub contrast: f64,
+    /// Fraction of pixels with clipped highlights.
+    pub overexposed: f64,
+    /// Fraction of pixels with crushed shadows.
+    pub underexposed: f64,
+}
+
+/// Limits a frame must satisfy to pass; `None` disables a check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Thresholds {
+    pub min_sharpness: Option<f64>,
+    pub min_contrast: Option<f64>,
+    pub max_overexposed: Option<f64>,
+    pub max_underexposed: Option<f64>,
+}
+
+/// A reason a frame failed the configured [`Thresholds`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Issue {
+    Blurry,
+    Blank,
+    Overexposed,
+    Underexposed,
+}
+
+impl QualityScores {
+    pub fn compute(img: &RgbImage) -> Self {
+        let luma = DynamicImage::ImageRgb8(img.clone()).into_luma8();
+        let n = (luma.len() as f64).max(1.0);
+
+        let (sum, sum_sq, over, under) =
+            luma.iter()
+                .fold((0f64, 0f64, 0usize, 0usize), |(s, sq, o, u), &v| {
+                    let f = f64::from(v);
+                    (
+                        s + f,
+                        sq + f * f,
+                        o + usize::from(v >= OVEREXPOSED_LUMA),
+                        u + usize::from(v <= UNDEREXPOSED_LUMA),
+                    )
+                });
+        let brightness = sum / n;
+
+        Self {
+            sharpness: laplacian_variance(&luma),
+            brightness,
+            contrast: (sum_sq / n - brightness * brightness).max(0.0).sqrt(),
+            overexposed: over as f64 / n,
+            underexposed: under as f64 / n,
+        }
+    }
+
+    /// Returns every threshold these scores violate.
+    pub fn issues(&self, thresholds: &Thresholds) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        if thresholds.min_sharpness.is_some_and(|t| self.sharpness < t) {
+            issues.push(Issue::Blurry);
+        }
+        if thresholds.min_contrast.is_some_and(|t| self.contrast < t) {
+            issues.push(Issue::Blank);
+        }
+        if thresholds
+            .max_overexposed
+            .is_some_and(|t| self.overexposed > t)
+        {
+            issues.push(Issue::Overexposed);
+        }
+        if thresholds
+            .max_underexposed
+            .is_some_and(|t| self.underexposed > t)
+        {
+            issues.push(Issue::Underexposed);
+        }
+        issues
+    }
+
+    /// Adds a `quality` object with the scores, `pass` and `issues` to a JSON-LD object.
+    pub fn extend_jsonld(&self, obj: &mut Map<String, Value>, issues: &[Issue]) {
+        if let Ok(Value::Object(mut quality)) = serde_json::to_value(self) {
+            quality.insert("pass".into(), issues.is_empty().into());
+            quality.insert(
+                "issues".into(),
+                serde_json::to_value(issues).unwrap_or_default(),
+            );
+            obj.insert("quality".into(), quality.into());
+        }
+    }
+}
+
+fn laplacian_variance(luma: &GrayImage) -> f64 {
+    let (w, h) = (luma.width() as usize, luma.height() as usize);
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+
+    let px = |x: usize, y: usize| f64::from(luma.as_raw()[y * w + x]);
+    let (mut sum, mut sum_sq, mut n) = (0f64, 0f64, 0f64);
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let l = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += l;
+            sum_sq += l * l;
+            n += 1.0;
+        }
+    }
+
+    let mean = sum / n;
+    (sum_sq / n - mean * mean).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn gray(width: u32, height: u32, f: impl Fn(u32, u32) -> u8) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let v = f(x, y);
+            Rgb([v, v, v])
+        })
+    }
+
+    fn checkerboard() -> RgbImage {
+        gray(4, 4, |x, y| if (x + y) % 2 == 0 { 0 } else { 255 })
+    }
+
+    #[test]
+    fn uniform_frame_is_flat() {
+        let scores = QualityScores::compute(&gray(8, 8, |_, _| 128));
+        assert_eq!(
+            scores,
+            QualityScores {
+                sharpness: 0.0,
+                brightness: 128.0,
+                contrast: 0.0,
+                overexposed: 0.0,
+                underexposed: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn checkerboard_is_sharp_and_clipped() {
+        let scores = QualityScores::compute(&checkerboard());
+        // Every interior Laplacian is ±4·255:
+        assert_eq!(scores.sharpness, 1020.0 * 1020.0);
+        assert_eq!(scores.brightness, 127.5);
+        assert_eq!(scores.contrast, 127.5);
+        assert_eq!(scores.overexposed, 0.5);
+        assert_eq!(scores.underexposed, 0.5);
+    }
+
+    #[test]
+    fn exposure_thresholds() {
+        assert_eq!(
+            QualityScores::compute(&gray(2, 2, |_, _| 255)).overexposed,
+            1.0
+        );
+        assert_eq!(
+            QualityScores::compute(&gray(2, 2, |_, _| 0)).underexposed,
+            1.0
+        );
+        // Too small for a Laplacian:
+        assert_eq!(
+            QualityScores::compute(&gray(2, 2, |x, _| x as u8 * 255)).sharpness,
+            0.0
+        );
+    }
+
+    #[test]
+    fn reports_violated_thresholds() {
+        let thresholds = Thresholds {
+            min_sharpness: Some(10.0),
+            min_contrast: Some(5.0),
+            max_overexposed: Some(0.25),
+            max_underexposed: Some(0.25),
+        };
+        let flat = QualityScores::compute(&gray(8, 8, |_, _| 128));
+        assert_eq!(flat.issues(&thresholds), [Issue::Blurry, Issue::Blank]);
+        let sharp = QualityScores::compute(&checkerboard());
+        assert_eq!(
+            sharp.issues(&thresholds),
+            [Issue::Overexposed, Issue::Underexposed]
+        );
+        assert!(flat.issues(&Thresholds::default()).is_empty());
+    }
+
+    #[test]
+    fn extends_jsonld_with_a_quality_object() {
+        let mut obj = Map::new();
+        let scores = QualityScores::compute(&gray(8, 8, |_, _| 128));
+        scores.extend_jsonld(&mut obj, &[Issue::Blank]);
+        assert_eq!(obj["quality"]["brightness"], 128.0);
+        assert_eq!(obj["quality"]["pass"], false);
+        assert_eq!(obj["quality"]["issues"], serde_json::json!(["blank"]));
+    }
+}
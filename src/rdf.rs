@@ -0,0 +1,245 @@
+// This is free and unencumbered software released into the public domain.
+
+//! N-Triples and Turtle serialization of image JSON-LD nodes.
+
+use serde_json::{Map, Value};
+use std::fmt::Write;
+
+/// The vocabulary of image properties and of any other plain-named property.
+pub const KNOW: &str = "https://know.dev/";
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Properties whose string values are IRIs rather than literals.
+const IRI_PROPERTIES: &[&str] = &["source"];
+
+/// An RDF term in the object position of a triple.
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Iri(String),
+    Blank(String),
+    Literal {
+        value: String,
+        /// `None` for a plain string.
+        datatype: Option<Datatype>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Datatype {
+    Integer,
+    Double,
+    Boolean,
+    Json,
+}
+
+impl Datatype {
+    fn iri(self) -> String {
+        match self {
+            Datatype::Integer => format!("{XSD}integer"),
+            Datatype::Double => format!("{XSD}double"),
+            Datatype::Boolean => format!("{XSD}boolean"),
+            Datatype::Json => format!("{RDF}JSON"),
+        }
+    }
+
+    fn prefixed(self) -> &'static str {
+        match self {
+            Datatype::Integer => "xsd:integer",
+            Datatype::Double => "xsd:double",
+            Datatype::Boolean => "xsd:boolean",
+            Datatype::Json => "rdf:JSON",
+        }
+    }
+}
+
+/// Serializes a JSON-LD node as N-Triples, one triple per line.
+///
+/// The subject is the node's `@id`, or a blank node. Plain property names are
+/// taken from the [KNOW] vocabulary, `source` is an IRI, numbers and booleans
+/// are typed literals, and nested arrays and objects become `rdf:JSON` literals.
+pub fn to_ntriples(node: &Value) -> String {
+    let (subject, triples) = triples(node);
+    let subject = ntriples_term(&subject);
+
+    let mut out = String::new();
+    for (predicate, object) in &triples {
+        let _ = writeln!(
+            out,
+            "{subject} <{}> {} .",
+            escape_iri(predicate),
+            ntriples_term(object)
+        );
+    }
+    out
+}
+
+/// Serializes a JSON-LD node as a Turtle document, using the same mapping as
+/// [`to_ntriples`] with `know:`, `rdf:` and `xsd:` prefixes.
+pub fn to_turtle(node: &Value) -> String {
+    let (subject, triples) = triples(node);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "@prefix know: <{KNOW}> .");
+    let _ = writeln!(out, "@prefix rdf: <{RDF}> .");
+    let _ = writeln!(out, "@prefix xsd: <{XSD}> .");
+    let _ = writeln!(out);
+
+    let _ = write!(out, "{}", turtle_term(&subject));
+    for (index, (predicate, object)) in triples.iter().enumerate() {
+        let predicate = if predicate == &format!("{RDF}type") {
+            "a".into()
+        } else {
+            turtle_iri(predicate)
+        };
+        let separator = if index == 0 { " " } else { " ;\n    " };
+        let _ = write!(out, "{separator}{predicate} {}", turtle_term(object));
+    }
+    let _ = writeln!(out, " .");
+    out
+}
+
+/// The subject and the (predicate, object) pairs of a node.
+fn triples(node: &Value) -> (Term, Vec<(String, Term)>) {
+    let empty = Map::new();
+    let node = node.as_object().unwrap_or(&empty);
+
+    let subject = match node.get("@id").and_then(Value::as_str) {
+        Some(id) => match id.strip_prefix("_:") {
+            Some(label) => Term::Blank(label.into()),
+            None => Term::Iri(id.into()),
+        },
+        None => Term::Blank("image".into()),
+    };
+
+    let mut triples = Vec::new();
+    for (key, value) in node {
+        if key == "@type" {
+            let types = match value {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                Value::String(t) => vec![t.as_str()],
+                _ => Vec::new(),
+            };
+            for t in types {
+                triples.push((format!("{RDF}type"), Term::Iri(expand(t))));
+            }
+            continue;
+        }
+        if key.starts_with('@') {
+            continue;
+        }
+
+        let object = match value {
+            Value::Null => continue,
+            Value::String(s) if IRI_PROPERTIES.contains(&key.as_str()) => Term::Iri(s.clone()),
+            Value::String(s) => Term::Literal {
+                value: s.clone(),
+                datatype: None,
+            },
+            Value::Bool(b) => Term::Literal {
+                value: b.to_string(),
+                datatype: Some(Datatype::Boolean),
+            },
+            Value::Number(n) if n.is_f64() => Term::Literal {
+                value: n.to_string(),
+                datatype: Some(Datatype::Double),
+            },
+            Value::Number(n) => Term::Literal {
+                value: n.to_string(),
+                datatype: Some(Datatype::Integer),
+            },
+            Value::Array(_) | Value::Object(_) => Term::Literal {
+                value: value.to_string(),
+                datatype: Some(Datatype::Json),
+            },
+        };
+        triples.push((expand(key), object));
+    }
+
+    (subject, triples)
+}
+
+/// Expands a plain name in the KNOW vocabulary; IRIs are kept.
+fn expand(name: &str) -> String {
+    if name.contains(':') {
+        name.into()
+    } else {
+        format!("{KNOW}{name}")
+    }
+}
+
+fn ntriples_term(term: &Term) -> String {
+    match term {
+        Term::Iri(iri) => format!("<{}>", escape_iri(iri)),
+        Term::Blank(label) => format!("_:{label}"),
+        Term::Literal {
+            value,
+            datatype: None,
+        } => format!("\"{}\"", escape_string(value)),
+        Term::Literal {
+            value,
+            datatype: Some(datatype),
+        } => format!("\"{}\"^^<{}>", escape_string(value), datatype.iri()),
+    }
+}
+
+fn turtle_term(term: &Term) -> String {
+    match term {
+        Term::Iri(iri) => turtle_iri(iri),
+        Term::Literal {
+            value,
+            datatype: Some(Datatype::Integer | Datatype::Boolean),
+        } => value.clone(),
+        Term::Literal {
+            value,
+            datatype: Some(datatype),
+        } => format!("\"{}\"^^{}", escape_string(value), datatype.prefixed()),
+        term => ntriples_term(term),
+    }
+}
+
+/// Abbreviates IRIs in the KNOW vocabulary to `know:` names where possible.
+fn turtle_iri(iri: &str) -> String {
+    match iri.strip_prefix(KNOW) {
+        Some(local) if is_local_name(local) => format!("know:{local}"),
+        _ => format!("<{}>", escape_iri(iri)),
+    }
+}
+
+fn is_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Percent-encodes the characters not allowed in an IRI, such as spaces in paths.
+fn escape_iri(iri: &str) -> String {
+    let mut out = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        match c {
+            '\0'..=' ' | '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => {
+                let _ = write!(out, "%{:02X}", c as u32);
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -3,15 +3,20 @@
 #[cfg(not(feature = "std"))]
 compile_error!("asimov-image-reader requires the 'std' feature");
 
-use asimov_image_module::core::{Error, Result as CoreResult, handle_error};
+use asimov_image_module::{
+    codec::Input,
+    core::{Error, ErrorOptions, Result as CoreResult, handle_error, init_error_output},
+    hash::{PerceptualHashes, sha256_hex},
+    jsonld::{self, ContextOptions},
+    rdf,
+    stats::ImageStats,
+};
 use asimov_module::SysexitsError::{self, *};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use clientele::StandardOptions;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 use know::traits::ToJsonLd;
 use std::error::Error as StdError;
-use std::io::Read;
-use std::path::PathBuf;
 
 /// asimov-image-reader
 #[derive(Debug, Parser)]
@@ -19,6 +24,12 @@ struct Options {
     #[clap(flatten)]
     flags: StandardOptions,
 
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    jsonld: ContextOptions,
+
     /// Input image file path.
     /// If not specified, reads from stdin
     url: Option<String>,
@@ -27,6 +38,53 @@ struct Options {
     /// If not specified, uses the input file's native dimensions
     #[arg(short = 's', long = "size", value_parser = parse_dimensions)]
     size: Option<(u32, u32)>,
+
+    /// Emit a SHA-256 hash of the source bytes as `sha256`
+    #[arg(long = "content-hash")]
+    content_hash: bool,
+
+    /// Emit perceptual hashes of the emitted pixels as `ahash`, `dhash` and `phash`
+    #[arg(long = "perceptual-hash")]
+    perceptual_hash: bool,
+
+    /// Emit histograms, mean/stddev, min/max, average and dominant colors of the emitted pixels
+    #[arg(long)]
+    stats: bool,
+
+    /// Number of dominant colors to extract with `--stats`
+    #[arg(long, value_name = "K", default_value_t = 5)]
+    colors: usize,
+
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Jsonld)]
+    output: OutputFormat,
+
+    /// Attach the pixel data as a `data` literal, or omit it
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = Pixels::Literal)]
+    pixels: Pixels,
+
+    /// Indent the JSON-LD and summarize the pixel data, for reading rather than piping
+    #[cfg(feature = "pretty")]
+    #[arg(short = 'p', long)]
+    pretty: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// One line of JSON-LD
+    Jsonld,
+    /// RDF triples, one per line
+    Ntriples,
+    /// An RDF document with `know:`, `rdf:` and `xsd:` prefixes
+    Turtle,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Pixels {
+    /// Include the pixels as a base64 data URL
+    Literal,
+    /// Leave out the pixels, keeping only the metadata
+    Omit,
 }
 
 pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
@@ -55,6 +113,11 @@ pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
     #[cfg(feature = "tracing")]
     asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
 
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
     let exit_code = match run_reader(&options) {
         Ok(()) => EX_OK,
         Err(err) => handle_error(&err, &options.flags),
@@ -72,19 +135,23 @@ fn run_reader(opts: &Options) -> CoreResult<()> {
         "starting reader"
     );
 
-    let (image_data, abs_path) = read_input_bytes(&opts.url)?;
+    let input = Input::read(opts.url.as_deref())?;
+    let abs_path = input.name();
 
     #[cfg(feature = "tracing")]
     asimov_module::tracing::debug!(
         target: "asimov_image_module::reader",
         path = %abs_path,
-        bytes = image_data.len(),
+        bytes = input.data.len(),
         "read input image bytes"
     );
 
-    let mut img = image::load_from_memory(&image_data)?;
+    let mut img = input.decode()?;
     let (src_w, src_h) = img.dimensions();
 
+    // The content hash identifies the source file:
+    let content_hash = opts.content_hash.then(|| sha256_hex(&input.data));
+
     #[cfg(feature = "tracing")]
     asimov_module::tracing::debug!(
         target: "asimov_image_module::reader",
@@ -93,22 +160,31 @@ fn run_reader(opts: &Options) -> CoreResult<()> {
         "decoded image"
     );
 
-    if let Some((target_w, target_h)) = opts.size {
-        if target_w != src_w || target_h != src_h {
-            #[cfg(feature = "tracing")]
-            asimov_module::tracing::debug!(
-                target: "asimov_image_module::reader",
-                target_width = target_w,
-                target_height = target_h,
-                "resizing image"
-            );
-
-            img = img.resize_exact(target_w, target_h, image::imageops::FilterType::Lanczos3);
-        }
+    if let Some((target_w, target_h)) = opts.size
+        && (target_w != src_w || target_h != src_h)
+    {
+        #[cfg(feature = "tracing")]
+        asimov_module::tracing::debug!(
+            target: "asimov_image_module::reader",
+            target_width = target_w,
+            target_height = target_h,
+            "resizing image"
+        );
+
+        img = img.resize_exact(target_w, target_h, image::imageops::FilterType::Lanczos3);
     }
 
-    let rgb_img = img.to_rgb8();
-    let (w, h) = rgb_img.dimensions();
+    // Pixel-derived properties describe the emitted pixels, after `--size`,
+    // so that they match what dedup and stats compute from the payload:
+    let img = DynamicImage::ImageRgb8(img.into_rgb8());
+    let (w, h) = img.dimensions();
+    let perceptual_hashes = opts
+        .perceptual_hash
+        .then(|| PerceptualHashes::compute(&img));
+    let rgb_img = img.into_rgb8();
+    let stats = opts
+        .stats
+        .then(|| ImageStats::compute(&rgb_img, opts.colors));
     let raw_data = rgb_img.into_raw();
 
     let file_url = format!("file:{abs_path}");
@@ -120,11 +196,44 @@ fn run_reader(opts: &Options) -> CoreResult<()> {
         source: Some(file_url),
     };
 
-    let jsonld = image
+    let mut jsonld = image
         .to_jsonld()
         .map_err(|e| Error::JsonLd(e.to_string()))?;
 
-    println!("{jsonld}");
+    if let Some(obj) = jsonld.as_object_mut() {
+        if let Some(hash) = content_hash {
+            obj.insert("sha256".into(), hash.into());
+        }
+        if let Some(hashes) = perceptual_hashes {
+            hashes.extend_jsonld(obj);
+        }
+        if let Some(stats) = stats {
+            stats.extend_jsonld(obj);
+        }
+    }
+
+    if opts.pixels == Pixels::Omit
+        && let Some(obj) = jsonld.as_object_mut()
+    {
+        obj.remove("data");
+    }
+
+    match opts.output {
+        OutputFormat::Jsonld => {
+            jsonld::set_context(&mut jsonld, opts.jsonld.context.as_ref());
+            #[cfg(feature = "pretty")]
+            let output = if opts.pretty {
+                jsonld::to_pretty_string(&jsonld)
+            } else {
+                jsonld.to_string()
+            };
+            #[cfg(not(feature = "pretty"))]
+            let output = jsonld.to_string();
+            println!("{output}");
+        },
+        OutputFormat::Ntriples => print!("{}", rdf::to_ntriples(&jsonld)),
+        OutputFormat::Turtle => print!("{}", rdf::to_turtle(&jsonld)),
+    }
 
     #[cfg(feature = "tracing")]
     asimov_module::tracing::info!(
@@ -137,42 +246,6 @@ fn run_reader(opts: &Options) -> CoreResult<()> {
     Ok(())
 }
 
-/// Read input from a file path (optionally prefixed by file:/file://) or from stdin.
-/// Returns (bytes, canonical_file_url).
-fn read_input_bytes(url: &Option<String>) -> CoreResult<(Vec<u8>, String)> {
-    if let Some(url) = url {
-        let input_path = {
-            let p = url;
-            let p = p.strip_prefix("file://").unwrap_or(p);
-            let p = p.strip_prefix("file:").unwrap_or(p);
-            p
-        };
-
-        let canonical = PathBuf::from(input_path)
-            .canonicalize()
-            .map_err(|e| Error::Io {
-                context: "resolving input path",
-                source: e,
-            })?;
-
-        let data = std::fs::read(input_path).map_err(|e| Error::Io {
-            context: "reading input file",
-            source: e,
-        })?;
-
-        Ok((data, canonical.to_string_lossy().to_string()))
-    } else {
-        let mut data = Vec::new();
-        std::io::stdin()
-            .read_to_end(&mut data)
-            .map_err(|e| Error::Io {
-                context: "reading from stdin",
-                source: e,
-            })?;
-        Ok((data, "[stdin]".to_string()))
-    }
-}
-
 /// Accepts "1920x1080", "1920×1080", with optional spaces. Validates reasonable ranges.
 fn parse_dimensions(s: &str) -> Result<(u32, u32), String> {
     let s = s.trim().replace('×', "x");
@@ -0,0 +1,277 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Per-channel statistics, histograms and dominant colors of RGB images.
+
+use image::RgbImage;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Upper bound on the number of pixels sampled for k-means clustering.
+const MAX_SAMPLES: usize = 4096;
+
+/// Number of k-means refinement iterations.
+const ITERATIONS: usize = 12;
+
+/// Summary statistics of an RGB image.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStats {
+    pub histogram: Histogram,
+    /// Per-channel mean, as `[r, g, b]`.
+    pub mean: [f64; 3],
+    /// Per-channel standard deviation, as `[r, g, b]`.
+    pub stddev: [f64; 3],
+    /// Per-channel minimum, as `[r, g, b]`.
+    pub min: [u8; 3],
+    /// Per-channel maximum, as `[r, g, b]`.
+    pub max: [u8; 3],
+    /// The mean color as `#rrggbb`.
+    pub average_color: String,
+    /// The k-means cluster centers, most frequent first.
+    pub dominant_colors: Vec<DominantColor>,
+}
+
+/// Per-channel 256-bin histograms.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    pub red: Vec<u64>,
+    pub green: Vec<u64>,
+    pub blue: Vec<u64>,
+}
+
+/// A dominant color and the fraction of sampled pixels closest to it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DominantColor {
+    pub color: String,
+    pub fraction: f64,
+}
+
+impl ImageStats {
+    /// Computes statistics of `img`, clustering its colors into at most `colors` groups.
+    pub fn compute(img: &RgbImage, colors: usize) -> Self {
+        let mut bins = [[0u64; 256]; 3];
+        let mut sum = [0f64; 3];
+        let mut sum_sq = [0f64; 3];
+        let mut min = [u8::MAX; 3];
+        let mut max = [u8::MIN; 3];
+
+        for px in img.pixels() {
+            for c in 0..3 {
+                let v = px.0[c];
+                bins[c][v as usize] += 1;
+                sum[c] += f64::from(v);
+                sum_sq[c] += f64::from(v) * f64::from(v);
+                min[c] = min[c].min(v);
+                max[c] = max[c].max(v);
+            }
+        }
+
+        let n = (img.width() as f64 * img.height() as f64).max(1.0);
+        let mean = sum.map(|s| s / n);
+        let stddev = [0, 1, 2].map(|c| (sum_sq[c] / n - mean[c] * mean[c]).max(0.0).sqrt());
+        if img.is_empty() {
+            min = [0; 3];
+        }
+
+        Self {
+            histogram: Histogram {
+                red: bins[0].to_vec(),
+                green: bins[1].to_vec(),
+                blue: bins[2].to_vec(),
+            },
+            mean,
+            stddev,
+            min,
+            max,
+            average_color: hex_color(mean),
+            dominant_colors: dominant_colors(img, colors),
+        }
+    }
+
+    /// Adds these statistics as properties of a JSON-LD object.
+    pub fn extend_jsonld(&self, obj: &mut Map<String, Value>) {
+        if let Ok(Value::Object(props)) = serde_json::to_value(self) {
+            obj.extend(props);
+        }
+    }
+}
+
+fn dominant_colors(img: &RgbImage, k: usize) -> Vec<DominantColor> {
+    let pixels = img.as_raw();
+    let count = pixels.len() / 3;
+    if count == 0 || k == 0 {
+        return Vec::new();
+    }
+
+    let step = count.div_ceil(MAX_SAMPLES);
+    let samples: Vec<[f64; 3]> = pixels
+        .chunks_exact(3)
+        .step_by(step)
+        .map(|p| [p[0], p[1], p[2]].map(f64::from))
+        .collect();
+
+    // Seed deterministically from luma quantiles so results are reproducible:
+    let mut by_luma = samples.clone();
+    by_luma.sort_by(|a, b| luma(a).total_cmp(&luma(b)));
+    let k = k.min(samples.len());
+    let mut centers: Vec<[f64; 3]> = (0..k)
+        .map(|i| by_luma[(2 * i + 1) * by_luma.len() / (2 * k)])
+        .collect();
+
+    let mut assignment = vec![0usize; samples.len()];
+    for _ in 0..ITERATIONS {
+        let mut changed = false;
+        for (sample, slot) in samples.iter().zip(assignment.iter_mut()) {
+            let nearest = nearest_center(sample, &centers);
+            changed |= *slot != nearest;
+            *slot = nearest;
+        }
+
+        let mut sums = vec![[0f64; 3]; k];
+        let mut counts = vec![0usize; k];
+        for (sample, &slot) in samples.iter().zip(&assignment) {
+            counts[slot] += 1;
+            for c in 0..3 {
+                sums[slot][c] += sample[c];
+            }
+        }
+        for ((center, sum), &n) in centers.iter_mut().zip(&sums).zip(&counts) {
+            if n > 0 {
+                *center = sum.map(|s| s / n as f64);
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut counts = vec![0usize; k];
+    for &slot in &assignment {
+        counts[slot] += 1;
+    }
+
+    let mut result: Vec<(usize, [f64; 3])> = counts
+        .into_iter()
+        .zip(centers)
+        .filter(|&(n, _)| n > 0)
+        .collect();
+    result.sort_by_key(|&(n, _)| std::cmp::Reverse(n));
+
+    result
+        .into_iter()
+        .map(|(n, center)| DominantColor {
+            color: hex_color(center),
+            fraction: n as f64 / samples.len() as f64,
+        })
+        .collect()
+}
+
+fn nearest_center(sample: &[f64; 3], centers: &[[f64; 3]]) -> usize {
+    centers
+        .iter()
+        .map(|center| (0..3).map(|c| (sample[c] - center[c]).powi(2)).sum::<f64>())
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn luma(rgb: &[f64; 3]) -> f64 {
+    0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2]
+}
+
+fn hex_color(rgb: [f64; 3]) -> String {
+    let [r, g, b] = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const RED: Rgb<u8> = Rgb([255, 0, 0]);
+    const BLUE: Rgb<u8> = Rgb([0, 0, 255]);
+
+    /// A 3x2 image of four red and two blue pixels.
+    fn red_and_blue() -> RgbImage {
+        RgbImage::from_fn(3, 2, |x, _| if x == 2 { BLUE } else { RED })
+    }
+
+    #[test]
+    fn histograms_count_each_channel() {
+        let stats = ImageStats::compute(&red_and_blue(), 2);
+        assert_eq!(stats.histogram.red[255], 4);
+        assert_eq!(stats.histogram.red[0], 2);
+        assert_eq!(stats.histogram.green[0], 6);
+        assert_eq!(stats.histogram.blue[255], 2);
+        assert_eq!(stats.histogram.blue[0], 4);
+        assert_eq!(stats.histogram.red.iter().sum::<u64>(), 6);
+    }
+
+    #[test]
+    fn mean_stddev_and_range() {
+        let stats = ImageStats::compute(&red_and_blue(), 2);
+        assert_eq!(stats.mean, [170.0, 0.0, 85.0]);
+        // Both channels have a variance of 255² · 4/6 · 2/6:
+        let stddev = (255.0f64 * 255.0 * 8.0 / 36.0).sqrt();
+        assert!((stats.stddev[0] - stddev).abs() < 1e-9);
+        assert_eq!(stats.stddev[1], 0.0);
+        assert!((stats.stddev[2] - stddev).abs() < 1e-9);
+        assert_eq!(stats.min, [0, 0, 0]);
+        assert_eq!(stats.max, [255, 0, 255]);
+        assert_eq!(stats.average_color, "#aa0055");
+    }
+
+    #[test]
+    fn dominant_colors_most_frequent_first() {
+        let stats = ImageStats::compute(&red_and_blue(), 2);
+        let colors: Vec<(&str, f64)> = stats
+            .dominant_colors
+            .iter()
+            .map(|c| (c.color.as_str(), c.fraction))
+            .collect();
+        assert_eq!(colors, [("#ff0000", 4.0 / 6.0), ("#0000ff", 2.0 / 6.0)]);
+
+        // More clusters than colors leave no empty cluster behind:
+        let stats = ImageStats::compute(&RgbImage::from_pixel(4, 4, RED), 3);
+        assert_eq!(stats.dominant_colors.len(), 1);
+        assert_eq!(stats.dominant_colors[0].fraction, 1.0);
+
+        assert!(
+            ImageStats::compute(&red_and_blue(), 0)
+                .dominant_colors
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn empty_image() {
+        let stats = ImageStats::compute(&RgbImage::new(0, 0), 5);
+        assert_eq!(stats.mean, [0.0; 3]);
+        assert_eq!(stats.min, [0; 3]);
+        assert_eq!(stats.max, [0; 3]);
+        assert!(stats.dominant_colors.is_empty());
+    }
+
+    #[test]
+    fn extends_jsonld_with_camel_case_properties() {
+        let mut obj = Map::new();
+        ImageStats::compute(&red_and_blue(), 2).extend_jsonld(&mut obj);
+        let keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            [
+                "histogram",
+                "mean",
+                "stddev",
+                "min",
+                "max",
+                "averageColor",
+                "dominantColors"
+            ]
+        );
+        assert_eq!(obj["histogram"]["green"][0], 6);
+    }
+}
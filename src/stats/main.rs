@@ -0,0 +1,155 @@
+// This is free and unencumbered software released into the public domain.
+
+#[cfg(not(feature = "std"))]
+compile_error!("asimov-image-stats requires the 'std' feature");
+
+use asimov_image_module::{
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, init_error_output,
+        warn_input_error, warn_user_with_error,
+    },
+    jsonld::{self, ContextOptions},
+    pixels,
+    stats::ImageStats,
+    stream::JsonStream,
+};
+use asimov_module::SysexitsError::{self, *};
+use clap::Parser;
+use clientele::StandardOptions;
+use know::classes::Image as KnowImage;
+use serde::Deserialize;
+use serde_json::Value;
+use std::error::Error as StdError;
+use std::io::{self, Write};
+
+/// asimov-image-stats
+#[derive(Debug, Parser)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    jsonld: ContextOptions,
+
+    /// Number of dominant colors to extract
+    #[arg(short = 'k', long, value_name = "K", default_value_t = 5)]
+    colors: usize,
+}
+
+pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
+    // Load environment variables from `.env`:
+    asimov_module::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
+    let exit_code = match run_stats(&options) {
+        Ok(()) => EX_OK,
+        Err(err) => handle_error(&err, &options.flags),
+    };
+
+    Ok(exit_code)
+}
+
+fn run_stats(opts: &Options) -> CoreResult<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let flags = &opts.flags;
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::stats",
+        colors = opts.colors,
+        "starting stats"
+    );
+
+    for record in JsonStream::new(stdin.lock()) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e @ Error::Parse { .. }) => {
+                warn_user_with_error(flags, "failed to parse Image JSON-LD", &e);
+                continue;
+            },
+            Err(e) => {
+                warn_user_with_error(flags, "stdin read error", &e);
+                break;
+            },
+        };
+        let input = InputContext {
+            line: Some(record.line),
+            image: None,
+        };
+        let mut value = record.value;
+
+        let stats = match image_stats(&value, opts.colors) {
+            Ok(stats) => stats,
+            Err(e) => {
+                let image = value.get("@id").and_then(Value::as_str);
+                warn_input_error(
+                    flags,
+                    "failed to compute image statistics",
+                    &e,
+                    InputContext { image, ..input },
+                );
+                continue;
+            },
+        };
+
+        if let Some(obj) = value.as_object_mut() {
+            stats.extend_jsonld(obj);
+        }
+
+        jsonld::set_context(&mut value, opts.jsonld.context.as_ref());
+        let output = serde_json::to_string(&value).map_err(|e| Error::JsonLd(e.to_string()))?;
+        writeln!(stdout, "{output}")
+            .and_then(|_| stdout.flush())
+            .map_err(|e| Error::Io {
+                context: "writing to stdout",
+                source: e,
+            })?;
+    }
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::info!(
+        target: "asimov_image_module::stats",
+        "stats exiting"
+    );
+
+    Ok(())
+}
+
+fn image_stats(value: &Value, colors: usize) -> CoreResult<ImageStats> {
+    let img = KnowImage::deserialize(value).map_err(|e| Error::Parse {
+        line: None,
+        message: e.to_string(),
+    })?;
+    let img = pixels::to_rgb_image(&img)?;
+    Ok(ImageStats::compute(&img, colors))
+}
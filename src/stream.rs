@@ -0,0 +1,456 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Streaming JSON-LD input: JSON lines, concatenated or pretty-printed
+//! objects, top-level arrays and `@graph` documents.
+
+use crate::core::{Error, Result};
+use crate::jsonld;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{BufRead, Read, Write};
+
+/// A JSON value read from the input, with the line it starts on.
+#[derive(Clone, Debug)]
+pub struct Record {
+    /// 1-based line number where the value starts.
+    pub line: usize,
+    pub value: Value,
+}
+
+/// Iterates over the JSON-LD image records in `input`, each normalized with
+/// [`jsonld::normalize_image`].
+///
+/// Values may be separated by any whitespace, so JSON lines, concatenated and
+/// pretty-printed objects are all accepted. The elements of a top-level array
+/// are read one at a time, and a `@graph` document yields each of its nodes,
+/// inheriting the document's `@context`. Only one value is buffered at a time.
+///
+/// Each value is delimited by its brackets and strings before it is parsed,
+/// so a malformed value yields a single `Error::Parse`, after which parsing
+/// resumes with the next value. An I/O error yields `Error::Io` and ends the
+/// stream.
+pub struct JsonStream<R> {
+    input: LineCounter<R>,
+    /// Inside a top-level array.
+    in_array: bool,
+    /// Nodes of a `@graph` document still to be yielded.
+    pending: VecDeque<Record>,
+    done: bool,
+}
+
+impl<R: BufRead> JsonStream<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input: LineCounter {
+                inner: input,
+                line: 1,
+            },
+            in_array: false,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the next value, stepping into and out of top-level arrays.
+    fn read_record(&mut self) -> Result<Option<Record>> {
+        loop {
+            let Some(byte) = self.skip_whitespace()? else {
+                return Ok(None);
+            };
+            match (byte, self.in_array) {
+                (b'[', false) => {
+                    self.input.consume(1);
+                    self.in_array = true;
+                },
+                (b',', true) => self.input.consume(1),
+                (b']', true) => {
+                    self.input.consume(1);
+                    self.in_array = false;
+                },
+                _ => break,
+            }
+        }
+
+        let line = self.input.line;
+        let bytes = self.scan_value()?;
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) => Ok(Some(Record { line, value })),
+            Err(e) => {
+                // Report the position relative to the whole input instead:
+                let message = e.to_string();
+                let position = format!(" at line {} column {}", e.line(), e.column());
+                Err(Error::Parse {
+                    line: Some(line + e.line().saturating_sub(1)),
+                    message: message.strip_suffix(&position).unwrap_or(&message).into(),
+                })
+            },
+        }
+    }
+
+    /// Consumes whitespace and returns the next byte without consuming it.
+    fn skip_whitespace(&mut self) -> Result<Option<u8>> {
+        loop {
+            let buf = self.input.fill_buf()?;
+            let Some(&byte) = buf.first() else {
+                return Ok(None);
+            };
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.input.consume(1);
+        }
+    }
+
+    /// Consumes the bytes of the next value: up to its matching closing bracket,
+    /// the end of a string or, for other values, the next delimiter. A mismatched
+    /// bracket or a line break inside a string ends the value early, so that
+    /// malformed input is skipped one value at a time.
+    fn scan_value(&mut self) -> Result<Vec<u8>> {
+        let mut scanner = Scanner::default();
+        let mut bytes = Vec::new();
+        loop {
+            let buf = self.input.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(bytes);
+            }
+            let (len, done) = scanner.scan(buf, self.in_array);
+            bytes.extend_from_slice(&buf[..len]);
+            self.input.consume(len);
+            if done {
+                return Ok(bytes);
+            }
+        }
+    }
+}
+
+/// Where [`JsonStream::scan_value`] is within a value.
+#[derive(Debug, Default)]
+struct Scanner {
+    /// Closing brackets expected, innermost last.
+    closers: Vec<u8>,
+    in_string: bool,
+    escaped: bool,
+    /// Whether any byte was scanned.
+    started: bool,
+}
+
+impl Scanner {
+    /// Scans `buf`, returning how many bytes belong to the value and whether
+    /// the value ends there.
+    fn scan(&mut self, buf: &[u8], in_array: bool) -> (usize, bool) {
+        for (index, &byte) in buf.iter().enumerate() {
+            let first = !self.started;
+            self.started = true;
+
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' | b'\n' => {
+                        self.in_string = false;
+                        if self.closers.is_empty() {
+                            return (index + 1, true);
+                        }
+                    },
+                    _ => {},
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' => self.closers.push(b'}'),
+                b'[' => self.closers.push(b']'),
+                b'}' | b']' => match self.closers.pop() {
+                    Some(closer) if closer == byte && !self.closers.is_empty() => {},
+                    // The value is complete, or a bracket is mismatched:
+                    Some(_) => return (index + 1, true),
+                    // A stray closing bracket is a malformed value of its own:
+                    None if first => return (index + 1, true),
+                    None => return (index, true),
+                },
+                // A scalar ends at whitespace or, inside an array, at its delimiters:
+                _ if self.closers.is_empty()
+                    && !first
+                    && (byte.is_ascii_whitespace() || (in_array && byte == b',')) =>
+                {
+                    return (index, true);
+                },
+                _ => {},
+            }
+        }
+        (buf.len(), false)
+    }
+}
+
+impl<R: BufRead> Iterator for JsonStream<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                return Some(normalize(record));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.read_record() {
+                Ok(Some(record)) => match split_graph(record) {
+                    // An empty `@graph` yields nothing:
+                    Ok(nodes) => self.pending = nodes,
+                    Err(record) => return Some(normalize(record)),
+                },
+                Ok(None) => self.done = true,
+                Err(err @ Error::Parse { .. }) => return Some(Err(err)),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                },
+            }
+        }
+    }
+}
+
+fn normalize(mut record: Record) -> Result<Record> {
+    match jsonld::normalize_image(&mut record.value) {
+        Ok(()) => Ok(record),
+        Err(Error::Parse { message, .. }) => Err(Error::Parse {
+            line: Some(record.line),
+            message,
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Splits a `@graph` document into its nodes, which inherit its `@context`.
+/// Returns other records unchanged as `Err`.
+fn split_graph(record: Record) -> std::result::Result<VecDeque<Record>, Record> {
+    let Record { line, value } = record;
+    let Value::Object(mut doc) = value else {
+        return Err(Record { line, value });
+    };
+    let graph = match doc.remove("@graph") {
+        Some(Value::Array(graph)) => graph,
+        Some(graph) => {
+            doc.insert("@graph".into(), graph);
+            return Err(Record {
+                line,
+                value: Value::Object(doc),
+            });
+        },
+        None => {
+            return Err(Record {
+                line,
+                value: Value::Object(doc),
+            });
+        },
+    };
+
+    let context = doc.get("@context");
+    Ok(graph
+        .into_iter()
+        .map(|mut node| {
+            if let (Some(context), Some(obj)) = (context, node.as_object_mut()) {
+                obj.entry("@context").or_insert_with(|| context.clone());
+            }
+            Record { line, value: node }
+        })
+        .collect())
+}
+
+/// A reader that copies every byte consumed from `inner` to `out` unchanged,
+/// for passing the input through (`--union`) while it is parsed. Errors
+/// writing to `out` are ignored, so that a closed pipe does not stop the input.
+pub struct Tee<R, W: Write> {
+    inner: R,
+    out: W,
+}
+
+impl<R: BufRead, W: Write> Tee<R, W> {
+    pub fn new(inner: R, out: W) -> Self {
+        Self { inner, out }
+    }
+}
+
+impl<R: BufRead, W: Write> Read for Tee<R, W> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let len = buf.len().min(out.len());
+        out[..len].copy_from_slice(&buf[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead, W: Write> BufRead for Tee<R, W> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            let _ = self.out.write_all(&buf[..amount]);
+        }
+        self.inner.consume(amount);
+    }
+}
+
+impl<R, W: Write> Drop for Tee<R, W> {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// A reader that counts the lines it has consumed.
+struct LineCounter<R> {
+    inner: R,
+    line: usize,
+}
+
+impl<R: BufRead> LineCounter<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        self.inner.fill_buf().map_err(|e| Error::Io {
+            context: "reading input",
+            source: e,
+        })
+    }
+
+    /// Consumes `amount` bytes of the buffer returned by `fill_buf`.
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.line += count_lines(&buf[..amount]);
+        }
+        self.inner.consume(amount);
+    }
+}
+
+fn count_lines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `@id` of each record, or `Err(line)` for a parse error.
+    fn ids(input: &str) -> Vec<std::result::Result<String, Option<usize>>> {
+        JsonStream::new(input.as_bytes())
+            .map(|record| match record {
+                Ok(record) => Ok(record.value["@id"].as_str().unwrap_or_default().into()),
+                Err(Error::Parse { line, .. }) => Err(line),
+                Err(e) => panic!("unexpected error: {e}"),
+            })
+            .collect()
+    }
+
+    fn image(id: &str) -> String {
+        format!(r#"{{"@type":"Image","@id":"{id}","width":1,"height":1}}"#)
+    }
+
+    #[test]
+    fn reads_json_lines() {
+        let input = format!("{}\n{}\n", image("_:a"), image("_:b"));
+        assert_eq!(ids(&input), [Ok("_:a".into()), Ok("_:b".into())]);
+    }
+
+    #[test]
+    fn reads_concatenated_values() {
+        let input = format!("{}{} {}", image("_:a"), image("_:b"), image("_:c"));
+        assert_eq!(
+            ids(&input),
+            [Ok("_:a".into()), Ok("_:b".into()), Ok("_:c".into())]
+        );
+    }
+
+    #[test]
+    fn reads_pretty_printed_values_with_their_lines() {
+        let input = "{\n  \"@type\": \"Image\",\n  \"@id\": \"_:a\"\n}\n{\n  \"@id\": \"_:b\"\n}\n";
+        let lines: Vec<usize> = JsonStream::new(input.as_bytes())
+            .map(|record| record.unwrap().line)
+            .collect();
+        assert_eq!(lines, [1, 5]);
+        assert_eq!(ids(input), [Ok("_:a".into()), Ok("_:b".into())]);
+    }
+
+    #[test]
+    fn reads_top_level_arrays() {
+        let input = format!(
+            "[\n{},\n{}\n]\n{}",
+            image("_:a"),
+            image("_:b"),
+            image("_:c")
+        );
+        assert_eq!(
+            ids(&input),
+            [Ok("_:a".into()), Ok("_:b".into()), Ok("_:c".into())]
+        );
+    }
+
+    #[test]
+    fn splits_graph_documents_inheriting_the_context() {
+        let input = r#"{"@context": {"@vocab": "https://schema.org/"},
+            "@graph": [
+                {"@type": "ImageObject", "@id": "_:a", "width": "2"},
+                {"@type": "ImageObject", "@id": "_:b"}
+            ]}"#;
+        let records: Vec<Record> = JsonStream::new(input.as_bytes())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].value["@type"], "Image");
+        assert_eq!(records[0].value["width"], 2);
+        assert_eq!(records[1].value["@id"], "_:b");
+    }
+
+    #[test]
+    fn reports_a_malformed_pretty_printed_value_once() {
+        let input = format!(
+            "{{\n  \"@type\": \"Image\",\n  \"@id\": \"_:bad\",\n  \"height\": oops,\n  \"width\": 1,\n  \"data\": \"\"\n}}\n{}\n",
+            image("_:good")
+        );
+        assert_eq!(ids(&input), [Err(Some(4)), Ok("_:good".into())]);
+    }
+
+    #[test]
+    fn resumes_after_malformed_values() {
+        let input = format!(
+            "{}\n{{\"@id\": [1, 2}}\n{{\"@id\": \"unterminated\n{}\n}}\n{}",
+            image("_:a"),
+            image("_:b"),
+            image("_:c")
+        );
+        // The unterminated string's object only closes on line 5, taking `_:b`
+        // with it; the error is reported where the string breaks:
+        assert_eq!(
+            ids(&input),
+            [
+                Ok("_:a".into()),
+                Err(Some(2)),
+                Err(Some(4)),
+                Ok("_:c".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn tee_copies_the_input_unchanged() {
+        let input = format!(
+            "{}\n{{\"@id\": oops}}\n{{\"@type\": \"Person\"}}\n",
+            image("_:a")
+        );
+        let mut copy = Vec::new();
+        let records = JsonStream::new(Tee::new(input.as_bytes(), &mut copy)).count();
+        assert_eq!(records, 3);
+        assert_eq!(String::from_utf8(copy).unwrap(), input);
+    }
+
+    #[test]
+    fn reports_non_image_nodes() {
+        let input = format!(
+            "{{\"@type\": \"Person\", \"@id\": \"_:p\"}}\n{}",
+            image("_:a")
+        );
+        assert_eq!(ids(&input), [Err(Some(1)), Ok("_:a".into())]);
+    }
+}
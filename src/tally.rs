@@ -0,0 +1,147 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Failure accounting for stream-processing loops.
+
+#[cfg(feature = "cli")]
+use crate::core::info_user;
+use crate::core::{Error, Result};
+#[cfg(feature = "cli")]
+use clientele::StandardOptions;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+/// Command-line options limiting how many input records may fail.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct FailureOptions {
+    /// Abort on the first failed input record, exiting with its error's status
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub strict: bool,
+
+    /// Abort once more than N input records have failed
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_name = "N", conflicts_with = "strict")
+    )]
+    pub max_errors: Option<usize>,
+}
+
+impl FailureOptions {
+    /// The number of failures tolerated, if limited.
+    pub fn budget(&self) -> Option<usize> {
+        if self.strict {
+            Some(0)
+        } else {
+            self.max_errors
+        }
+    }
+}
+
+/// Counts input records as they are read, parsed and processed, and enforces
+/// the failure budget. Safe to share between a reader thread and its consumer.
+#[derive(Debug, Default)]
+pub struct Tally {
+    read: AtomicUsize,
+    parsed: AtomicUsize,
+    processed: AtomicUsize,
+    skipped: AtomicUsize,
+    failed: AtomicUsize,
+    budget: Option<usize>,
+    fatal: Mutex<Option<Error>>,
+}
+
+impl Tally {
+    pub fn new(opts: &FailureOptions) -> Self {
+        Self {
+            budget: opts.budget(),
+            ..Self::default()
+        }
+    }
+
+    pub fn record_read(&self) {
+        self.read.fetch_add(1, Relaxed);
+    }
+
+    pub fn record_parsed(&self) {
+        self.parsed.fetch_add(1, Relaxed);
+    }
+
+    pub fn record_processed(&self) {
+        self.processed.fetch_add(1, Relaxed);
+    }
+
+    /// Counts a valid record that was deliberately left unprocessed.
+    pub fn record_skipped(&self) {
+        self.skipped.fetch_add(1, Relaxed);
+    }
+
+    /// Counts a failed record. Returns `error` if the failure budget is exceeded.
+    pub fn record_failure(&self, error: Error) -> Result<()> {
+        if self.count_failure() {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts a failed record. Returns whether the failure budget is exceeded,
+    /// for callers that keep the error.
+    pub fn count_failure(&self) -> bool {
+        let failed = self.failed.fetch_add(1, Relaxed) + 1;
+        self.budget.is_some_and(|budget| failed > budget)
+    }
+
+    /// Stores an error that ends processing, for another thread to pick up.
+    pub fn abort(&self, error: Error) {
+        if let Ok(mut fatal) = self.fatal.lock() {
+            fatal.get_or_insert(error);
+        }
+    }
+
+    /// Takes the error stored by `abort`, if any.
+    pub fn take_fatal(&self) -> Option<Error> {
+        self.fatal.lock().ok()?.take()
+    }
+
+    pub fn parsed(&self) -> usize {
+        self.parsed.load(Relaxed)
+    }
+
+    pub fn processed(&self) -> usize {
+        self.processed.load(Relaxed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.load(Relaxed)
+    }
+
+    /// Reports the counts, naming processed records by `processed`
+    /// (e.g. "saved" or "displayed").
+    #[cfg(feature = "cli")]
+    pub fn report(&self, flags: &StandardOptions, processed: &str) {
+        let (read, parsed, done, skipped, failed) = (
+            self.read.load(Relaxed),
+            self.parsed.load(Relaxed),
+            self.processed.load(Relaxed),
+            self.skipped.load(Relaxed),
+            self.failed.load(Relaxed),
+        );
+
+        let mut summary = format!("{read} read, {parsed} parsed, {done} {processed}");
+        if skipped > 0 {
+            summary += &format!(", {skipped} skipped");
+        }
+        info_user(flags, &format!("{summary}, {failed} failed"));
+
+        #[cfg(feature = "tracing")]
+        asimov_module::tracing::info!(
+            target: "asimov_image_module",
+            read,
+            parsed,
+            processed = done,
+            skipped,
+            failed,
+            "summary"
+        );
+    }
+}
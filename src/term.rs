@@ -0,0 +1,258 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Encoders that draw RGB images directly in a terminal.
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use image::{RgbImage, imageops::FilterType};
+use std::fmt::Write;
+
+/// Maximum payload per kitty graphics escape sequence.
+const KITTY_CHUNK: usize = 4096;
+
+/// Approximate width of a terminal cell in pixels, used to size sixel output.
+const CELL_WIDTH_PX: u32 = 8;
+
+/// A terminal graphics protocol.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Protocol {
+    /// Unicode upper half blocks with 24-bit ANSI colors; works almost everywhere.
+    #[default]
+    Halfblock,
+    /// The kitty terminal graphics protocol.
+    Kitty,
+    /// DEC sixel graphics.
+    Sixel,
+}
+
+impl Protocol {
+    /// Encodes `img` to fit within `cols` terminal columns. Only the
+    /// half-block encoder also honors a `rows` limit.
+    pub fn encode(self, img: &RgbImage, cols: u32, rows: Option<u32>) -> String {
+        match self {
+            Self::Halfblock => halfblock(img, cols, rows),
+            Self::Kitty => kitty(img, cols),
+            Self::Sixel => sixel(img, cols),
+        }
+    }
+}
+
+/// Draws `img` with `▀` characters, each cell showing two vertically stacked
+/// pixels, scaled to at most `cols` columns and (optionally) `rows` rows.
+pub fn halfblock(img: &RgbImage, cols: u32, rows: Option<u32>) -> String {
+    let img = fit(img, cols.max(1), rows.map(|r| r.max(1) * 2));
+    let mut out = String::new();
+
+    for y in (0..img.height()).step_by(2) {
+        for x in 0..img.width() {
+            let [r, g, b] = img.get_pixel(x, y).0;
+            let _ = write!(out, "\x1b[38;2;{r};{g};{b}m");
+            if y + 1 < img.height() {
+                let [r, g, b] = img.get_pixel(x, y + 1).0;
+                let _ = write!(out, "\x1b[48;2;{r};{g};{b}m");
+            }
+            out.push('▀');
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+/// Transmits `img` as raw RGB using the kitty graphics protocol, displayed
+/// across `cols` columns.
+pub fn kitty(img: &RgbImage, cols: u32) -> String {
+    let payload = STANDARD.encode(img.as_raw());
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},c={cols},m={more};{chunk}\x1b\\",
+                img.width(),
+                img.height()
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out.push('\n');
+
+    out
+}
+
+/// Encodes `img` as DEC sixel graphics using a 6x6x6 color cube, scaled to
+/// roughly `cols` columns.
+pub fn sixel(img: &RgbImage, cols: u32) -> String {
+    let img = fit(img, cols.max(1) * CELL_WIDTH_PX, None);
+    let (w, h) = img.dimensions();
+    let palette: Vec<usize> = img
+        .pixels()
+        .map(|px| {
+            let [r, g, b] = px.0.map(|c| (usize::from(c) * 5 + 127) / 255);
+            r * 36 + g * 6 + b
+        })
+        .collect();
+    let index = |x: u32, y: u32| palette[(y * w + x) as usize];
+
+    let mut out = format!("\x1bPq\"1;1;{w};{h}");
+    for i in 0..216u16 {
+        let [r, g, b] = [i / 36, (i / 6) % 6, i % 6].map(|c| c * 20);
+        let _ = write!(out, "#{i};2;{r};{g};{b}");
+    }
+
+    for band in (0..h).step_by(6) {
+        let band_h = (h - band).min(6);
+        let mut used = [false; 216];
+        for y in band..band + band_h {
+            for x in 0..w {
+                used[index(x, y)] = true;
+            }
+        }
+
+        for color in (0..216).filter(|&c| used[c]) {
+            let _ = write!(out, "#{color}");
+            let mut run: Option<(char, u32)> = None;
+            for x in 0..w {
+                let bits = (0..band_h)
+                    .filter(|&dy| index(x, band + dy) == color)
+                    .fold(0u8, |acc, dy| acc | (1 << dy));
+                let c = char::from(63 + bits);
+                run = match run {
+                    Some((prev, n)) if prev == c => Some((prev, n + 1)),
+                    Some((prev, n)) => {
+                        push_run(&mut out, prev, n);
+                        Some((c, 1))
+                    },
+                    None => Some((c, 1)),
+                };
+            }
+            if let Some((c, n)) = run {
+                push_run(&mut out, c, n);
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\\n");
+
+    out
+}
+
+fn push_run(out: &mut String, c: char, n: u32) {
+    if n > 3 {
+        let _ = write!(out, "!{n}{c}");
+    } else {
+        (0..n).for_each(|_| out.push(c));
+    }
+}
+
+/// Downscales `img` to fit within `max_w` x `max_h`, preserving aspect ratio.
+fn fit(img: &RgbImage, max_w: u32, max_h: Option<u32>) -> RgbImage {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return img.clone();
+    }
+
+    let mut scale = (f64::from(max_w) / f64::from(w)).min(1.0);
+    if let Some(max_h) = max_h {
+        scale = scale.min(f64::from(max_h) / f64::from(h));
+    }
+    if scale >= 1.0 {
+        return img.clone();
+    }
+
+    let nw = ((f64::from(w) * scale).round() as u32).max(1);
+    let nh = ((f64::from(h) * scale).round() as u32).max(1);
+    image::imageops::resize(img, nw, nh, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// A 2x3 image: red, green / blue, white / black, gray.
+    fn sample() -> RgbImage {
+        let pixels = [
+            [255, 0, 0],
+            [0, 255, 0],
+            [0, 0, 255],
+            [255, 255, 255],
+            [0, 0, 0],
+            [128, 128, 128],
+        ];
+        RgbImage::from_fn(2, 3, |x, y| Rgb(pixels[(y * 2 + x) as usize]))
+    }
+
+    #[test]
+    fn halfblock_pairs_rows_and_leaves_an_odd_last_row_without_background() {
+        let expected = concat!(
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀",
+            "\x1b[38;2;0;255;0m\x1b[48;2;255;255;255m▀",
+            "\x1b[0m\n",
+            "\x1b[38;2;0;0;0m▀",
+            "\x1b[38;2;128;128;128m▀",
+            "\x1b[0m\n",
+        );
+        assert_eq!(Protocol::Halfblock.encode(&sample(), 80, None), expected);
+    }
+
+    #[test]
+    fn halfblock_downscales_to_the_columns_and_rows() {
+        let img = RgbImage::from_pixel(8, 8, Rgb([10, 20, 30]));
+        let cell = "\x1b[38;2;10;20;30m\x1b[48;2;10;20;30m▀";
+        let row = format!("{cell}{cell}\x1b[0m\n");
+        assert_eq!(halfblock(&img, 2, None), row);
+        assert_eq!(halfblock(&img, 4, Some(1)), row);
+    }
+
+    #[test]
+    fn kitty_frames_a_single_chunk() {
+        let img = RgbImage::from_pixel(1, 2, Rgb([255, 0, 0]));
+        assert_eq!(
+            kitty(&img, 10),
+            "\x1b_Ga=T,f=24,s=1,v=2,c=10,m=0;/wAA/wAA\x1b\\\n"
+        );
+    }
+
+    #[test]
+    fn kitty_splits_large_payloads_into_chunks() {
+        // 1025 pixels are 3075 bytes, or 4100 base64 characters:
+        let img = RgbImage::from_pixel(1025, 1, Rgb([0, 0, 0]));
+        let out = kitty(&img, 40);
+        let chunks: Vec<&str> = out.trim_end().split_terminator("\x1b\\").collect();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("\x1b_Ga=T,f=24,s=1025,v=1,c=40,m=1;"));
+        assert_eq!(chunks[0].split_once(';').unwrap().1.len(), KITTY_CHUNK);
+        assert_eq!(chunks[1], "\x1b_Gm=0;AAAA");
+    }
+
+    #[test]
+    fn sixel_frames_bands_per_color() {
+        let img = RgbImage::from_fn(2, 1, |x, _| Rgb(if x == 0 { [255, 0, 0] } else { [0; 3] }));
+        let out = sixel(&img, 80);
+        assert!(out.starts_with("\x1bPq\"1;1;2;1#0;2;0;0;0#1;2;0;0;20"));
+        assert!(out.contains("#180;2;100;0;0#181;"));
+        // Black (0) covers the second pixel, red (180) the first:
+        assert!(out.ends_with("#215;2;100;100;100#0?@$#180@?$-\x1b\\\n"));
+    }
+
+    #[test]
+    fn sixel_run_length_encodes_and_splits_six_pixel_bands() {
+        let img = RgbImage::from_pixel(5, 7, Rgb([0, 0, 0]));
+        let out = sixel(&img, 80);
+        // A full band sets all six bits (`~`), the last band only its first row (`@`):
+        assert!(out.ends_with("#0!5~$-#0!5@$-\x1b\\\n"));
+    }
+
+    #[test]
+    fn sixel_downscales_to_the_columns() {
+        let img = RgbImage::from_pixel(64, 32, Rgb([0, 0, 0]));
+        assert!(sixel(&img, 2).starts_with("\x1bPq\"1;1;16;8#"));
+    }
+}
@@ -0,0 +1,90 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::frame::Frame;
+use asimov_image_module::{
+    core::{Error, Result as CoreResult},
+    jsonld, pixels,
+};
+use image::RgbImage;
+use know::traits::ToJsonLd;
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Saves the frame's pixels at full resolution as a timestamped PNG in `dir`.
+pub fn save_frame(dir: &Path, frame: &Frame) -> CoreResult<PathBuf> {
+    save_png(dir, &pixels::to_rgb_image(&frame.image)?)
+}
+
+/// Saves the `win`-sized 0RGB view, as currently displayed, as a timestamped PNG in `dir`.
+pub fn save_view(dir: &Path, canvas: &[u32], win: (usize, usize)) -> CoreResult<PathBuf> {
+    let data = canvas
+        .iter()
+        .flat_map(|px| [(px >> 16) as u8, (px >> 8) as u8, *px as u8])
+        .collect();
+    let rgb = RgbImage::from_raw(win.0 as u32, win.1 as u32, data)
+        .ok_or_else(|| Error::InvalidBuffer("view buffer does not match window size".into()))?;
+    save_png(dir, &rgb)
+}
+
+/// Writes the frame as a single line of JSON-LD to stdout, including any
+/// properties beyond the image itself, such as annotations.
+pub fn print_jsonld(frame: &Frame, context: Option<&Value>) -> CoreResult<()> {
+    let mut value = frame
+        .image
+        .to_jsonld()
+        .map_err(|e| Error::JsonLd(format!("failed to serialize image JSON-LD: {e}")))?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.extend(frame.properties.clone());
+    }
+    jsonld::set_context(&mut value, context);
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{value}")
+        .and_then(|_| stdout.flush())
+        .map_err(|e| Error::Io {
+            context: "writing to stdout",
+            source: e,
+        })
+}
+
+fn save_png(dir: &Path, rgb: &RgbImage) -> CoreResult<PathBuf> {
+    fs::create_dir_all(dir).map_err(|e| Error::Io {
+        context: "creating screenshot directory",
+        source: e,
+    })?;
+
+    let path = dir.join(format!("screenshot-{}.png", utc_timestamp()));
+    rgb.save(&path).map_err(|e| Error::saving(&path, None, e))?;
+    Ok(path)
+}
+
+/// The current UTC time as `YYYYMMDD-HHMMSS-mmm`, which sorts chronologically.
+fn utc_timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm):
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
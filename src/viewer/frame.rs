@@ -0,0 +1,57 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::overlay::Annotation;
+use know::classes::Image as KnowImage;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::time::{Duration, Instant};
+
+/// A received image together with its timing information and annotations.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub image: KnowImage,
+    /// When the frame was read from stdin.
+    pub received: Instant,
+    /// Capture time in seconds, from the `timestamp` property.
+    pub timestamp: Option<f64>,
+    /// How long to show the frame, from the `delay` property in milliseconds.
+    pub delay: Option<Duration>,
+    /// Shapes to draw over the image, from the `annotations` property.
+    pub annotations: Vec<Annotation>,
+    /// Properties other than the image's own, kept for re-emitting the frame.
+    pub properties: Map<String, Value>,
+}
+
+/// Properties represented by `KnowImage::to_jsonld`.
+const IMAGE_PROPERTIES: &[&str] = &["@type", "@id", "width", "height", "data", "source"];
+
+impl Frame {
+    /// Parses a frame from a JSON-LD image object.
+    pub fn from_value(value: &Value) -> serde_json::Result<Self> {
+        let image = KnowImage::deserialize(value)?;
+        let timestamp = value
+            .get("timestamp")
+            .and_then(Value::as_f64)
+            .filter(|t| t.is_finite());
+        let delay = value
+            .get("delay")
+            .and_then(Value::as_f64)
+            // Negative, non-finite and out-of-range delays count as missing:
+            .and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok());
+
+        Ok(Self {
+            image,
+            received: Instant::now(),
+            timestamp,
+            delay,
+            annotations: Annotation::parse_all(value),
+            properties: value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(k, _)| !IMAGE_PROPERTIES.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,129 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{font, show_image, viewport::Viewport};
+use asimov_image_module::core::Result as CoreResult;
+use clap::ValueEnum;
+use know::classes::Image as KnowImage;
+
+/// Gap between tiles, in window pixels.
+const GUTTER: usize = 2;
+
+const LABEL_COLOR: u32 = 0xffffff;
+const LABEL_BACKGROUND: u32 = 0x202020;
+
+/// The image property used to assign frames to tiles.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    /// The image `@id`
+    Id,
+    /// The image `source`, falling back to `@id`
+    Source,
+}
+
+/// A mosaic of the latest frame of each stream, in order of first appearance.
+#[derive(Debug)]
+pub struct Grid {
+    group_by: GroupBy,
+    columns: Option<usize>,
+    tiles: Vec<Tile>,
+}
+
+#[derive(Debug)]
+struct Tile {
+    key: String,
+    buffer: Vec<u32>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    pub fn new(group_by: GroupBy, columns: Option<usize>) -> Self {
+        Self {
+            group_by,
+            columns,
+            tiles: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Replaces the frame shown in the tile of `img`'s stream.
+    pub fn update(&mut self, img: &KnowImage) -> CoreResult<()> {
+        let key = match self.group_by {
+            GroupBy::Id => img.id.as_deref(),
+            GroupBy::Source => img.source.as_deref().or(img.id.as_deref()),
+        }
+        .unwrap_or("unknown");
+
+        let index = match self.tiles.iter().position(|t| t.key == key) {
+            Some(index) => index,
+            None => {
+                self.tiles.push(Tile {
+                    key: key.to_string(),
+                    buffer: Vec::new(),
+                    width: 0,
+                    height: 0,
+                });
+                self.tiles.len() - 1
+            },
+        };
+
+        let tile = &mut self.tiles[index];
+        let result = show_image(&mut tile.buffer, &mut tile.width, &mut tile.height, img);
+        if result.is_err() && tile.buffer.is_empty() {
+            self.tiles.remove(index);
+        }
+        result
+    }
+
+    /// Lays out all tiles in `dst`, a `win`-sized buffer, each fitted to its cell and labeled.
+    pub fn render(&self, dst: &mut [u32], win: (usize, usize), background: u32) {
+        dst.fill(background);
+        if self.tiles.is_empty() || win.0 == 0 || win.1 == 0 {
+            return;
+        }
+
+        let n = self.tiles.len();
+        let cols = self
+            .columns
+            .unwrap_or_else(|| (n as f64).sqrt().ceil() as usize)
+            .clamp(1, n);
+        let rows = n.div_ceil(cols);
+        let cell_w = (win.0 / cols).saturating_sub(GUTTER).max(1);
+        let cell_h = (win.1 / rows).saturating_sub(GUTTER).max(1);
+
+        let mut cell = vec![0u32; cell_w * cell_h];
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let (x0, y0) = ((i % cols) * (win.0 / cols), (i / cols) * (win.1 / rows));
+            Viewport::default().render(
+                &tile.buffer,
+                (tile.width, tile.height),
+                &mut cell,
+                (cell_w, cell_h),
+                background,
+            );
+
+            for (row, src) in cell.chunks_exact(cell_w).enumerate() {
+                let y = y0 + row;
+                if y >= win.1 {
+                    break;
+                }
+                let w = cell_w.min(win.0.saturating_sub(x0));
+                dst[y * win.0 + x0..][..w].copy_from_slice(&src[..w]);
+            }
+
+            let label = format!("{} {}x{}", tile.key, tile.width, tile.height);
+            let max_chars = cell_w / (font::GLYPH_WIDTH + 1);
+            let label: String = if label.chars().count() > max_chars {
+                // Keep the end, which is the most distinctive part of URLs and paths:
+                let skip = label.chars().count() - max_chars;
+                label.chars().skip(skip).collect()
+            } else {
+                label
+            };
+            font::draw_label(dst, win, (x0, y0), &label, 1, LABEL_COLOR, LABEL_BACKGROUND);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::pacing::FrameReceiver;
+use asimov_image_module::{
+    core::{Error, Result as CoreResult, warn_user_with_error},
+    pixels,
+    tally::Tally,
+    term::Protocol,
+};
+use clientele::StandardOptions;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+/// Terminal width used when it can be neither detected nor is given.
+const DEFAULT_COLUMNS: u32 = 80;
+
+/// Saves every received frame as `frame-NNNNNN.png` in `dir`, until the
+/// input ends or the failure budget is exceeded.
+pub fn run_snapshots(
+    rx: FrameReceiver,
+    dir: &Path,
+    flags: &StandardOptions,
+    tally: &Tally,
+) -> CoreResult<()> {
+    fs::create_dir_all(dir).map_err(|e| Error::Io {
+        context: "creating snapshot directory",
+        source: e,
+    })?;
+
+    for (index, frame) in (&rx).enumerate() {
+        let path = dir.join(format!("frame-{:06}.png", index + 1));
+        let result = pixels::to_rgb_image(&frame.image).and_then(|rgb| {
+            rgb.save(&path)
+                .map_err(|e| Error::saving(&path, frame.image.id.as_deref(), e))
+        });
+        if let Err(e) = result {
+            warn_user_with_error(flags, "failed to save snapshot", &e);
+            tally.record_failure(e)?;
+            continue;
+        }
+        tally.record_processed();
+
+        #[cfg(feature = "tracing")]
+        asimov_module::tracing::debug!(
+            target: "asimov_image_module::viewer",
+            path = %path.display(),
+            "saved snapshot"
+        );
+    }
+
+    Ok(())
+}
+
+/// Draws every received frame on stdout using a terminal graphics protocol.
+///
+/// On an interactive terminal each frame replaces the previous one and, unless
+/// given explicitly, the size follows the terminal. Otherwise frames are written
+/// one after another at a fixed default width with no cursor control sequences,
+/// so the output is deterministic.
+pub fn run_terminal(
+    rx: FrameReceiver,
+    protocol: Protocol,
+    size: (Option<u32>, Option<u32>),
+    flags: &StandardOptions,
+    tally: &Tally,
+) -> CoreResult<()> {
+    let mut stdout = io::stdout().lock();
+    let interactive = stdout.is_terminal();
+
+    for (index, frame) in (&rx).enumerate() {
+        let rgb = match pixels::to_rgb_image(&frame.image) {
+            Ok(rgb) => rgb,
+            Err(e) => {
+                warn_user_with_error(flags, "failed to display image", &e);
+                tally.record_failure(e)?;
+                continue;
+            },
+        };
+
+        let mut out = String::new();
+        if interactive {
+            // Clear once, then redraw in place from the top-left corner:
+            out.push_str(if index == 0 {
+                "\x1b[2J\x1b[H"
+            } else {
+                "\x1b[H"
+            });
+        }
+        let (cols, rows) = terminal_fit(size, interactive);
+        out.push_str(&protocol.encode(&rgb, cols, rows));
+
+        stdout
+            .write_all(out.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| Error::Io {
+                context: "writing to stdout",
+                source: e,
+            })?;
+        tally.record_processed();
+    }
+
+    Ok(())
+}
+
+/// Resolves the columns and rows to draw into, queried per frame so that
+/// resizing the terminal takes effect on the next frame.
+fn terminal_fit(size: (Option<u32>, Option<u32>), interactive: bool) -> (u32, Option<u32>) {
+    let detected = interactive
+        .then(terminal_size::terminal_size)
+        .flatten()
+        .map(|(w, h)| (u32::from(w.0), u32::from(h.0)));
+
+    let cols = size
+        .0
+        .or(detected.map(|(w, _)| w))
+        .unwrap_or(DEFAULT_COLUMNS);
+    // Leave the last row free so the trailing newline does not scroll the frame:
+    let rows = size.1.or(detected.map(|(_, h)| h.saturating_sub(1).max(1)));
+    (cols, rows)
+}
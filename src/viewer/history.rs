@@ -0,0 +1,178 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::frame::Frame;
+use std::collections::VecDeque;
+
+/// Bounded ring buffer of received frames with a playback cursor.
+///
+/// While live, the cursor follows the newest frame. When paused, incoming
+/// frames are still recorded but the cursor stays put until stepped.
+#[derive(Debug)]
+pub struct History {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    cursor: usize,
+    paused: bool,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity.min(1024)),
+            capacity: capacity.max(1),
+            cursor: 0,
+            paused: false,
+        }
+    }
+
+    /// Records a frame, evicting the oldest one if full. Returns whether the
+    /// current frame changed.
+    pub fn push(&mut self, frame: Frame) -> bool {
+        let mut evicted_current = false;
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+            evicted_current = self.cursor == 0;
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+        self.frames.push_back(frame);
+
+        if self.paused {
+            evicted_current
+        } else {
+            self.cursor = self.frames.len() - 1;
+            true
+        }
+    }
+
+    pub fn current(&self) -> Option<&Frame> {
+        self.frames.get(self.cursor)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Toggles pause. Resuming jumps back to the newest frame.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            self.last();
+        }
+    }
+
+    /// Steps one frame forward; pauses playback.
+    pub fn step_forward(&mut self) {
+        self.paused = true;
+        self.seek(self.cursor + 1);
+    }
+
+    /// Steps one frame backward; pauses playback.
+    pub fn step_backward(&mut self) {
+        self.paused = true;
+        self.seek(self.cursor.saturating_sub(1));
+    }
+
+    /// Jumps to the oldest recorded frame; pauses playback.
+    pub fn first(&mut self) {
+        self.paused = true;
+        self.seek(0);
+    }
+
+    /// Jumps to the newest recorded frame.
+    pub fn last(&mut self) {
+        self.seek(self.frames.len().saturating_sub(1));
+    }
+
+    /// Returns the 1-based position of the cursor and the number of recorded frames.
+    pub fn position(&self) -> (usize, usize) {
+        (self.cursor + 1, self.frames.len())
+    }
+
+    fn seek(&mut self, index: usize) {
+        if index < self.frames.len() {
+            self.cursor = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(n: usize) -> Frame {
+        Frame::from_value(&json!({
+            "@type": "Image",
+            "@id": format!("_:frame{n}"),
+            "width": 1,
+            "height": 1,
+            "data": "data:image/rgb;base64,AAAA",
+        }))
+        .unwrap()
+    }
+
+    fn history(frames: usize, capacity: usize) -> History {
+        let mut history = History::new(capacity);
+        for n in 1..=frames {
+            history.push(frame(n));
+        }
+        history
+    }
+
+    fn current(history: &History) -> &str {
+        history.current().unwrap().image.id.as_deref().unwrap()
+    }
+
+    #[test]
+    fn follows_the_newest_frame_while_live() {
+        let mut history = history(3, 8);
+        assert_eq!(history.position(), (3, 3));
+        assert!(history.push(frame(4)));
+        assert_eq!(history.position(), (4, 4));
+        assert_eq!(current(&history), "_:frame4");
+    }
+
+    #[test]
+    fn every_move_updates_the_position() {
+        let mut history = history(4, 8);
+        history.step_backward();
+        assert!(history.is_paused());
+        assert_eq!(history.position(), (3, 4));
+        history.first();
+        assert_eq!(history.position(), (1, 4));
+        history.step_backward();
+        assert_eq!(history.position(), (1, 4));
+        history.step_forward();
+        assert_eq!(history.position(), (2, 4));
+        history.last();
+        assert_eq!(history.position(), (4, 4));
+        assert!(history.is_paused());
+        history.step_forward();
+        assert_eq!(history.position(), (4, 4));
+    }
+
+    #[test]
+    fn records_frames_while_paused() {
+        let mut history = history(2, 8);
+        history.toggle_pause();
+        assert!(!history.push(frame(3)));
+        assert_eq!(history.position(), (2, 3));
+        assert_eq!(current(&history), "_:frame2");
+        history.toggle_pause();
+        assert_eq!(history.position(), (3, 3));
+    }
+
+    #[test]
+    fn evicts_the_oldest_frame_when_full() {
+        let mut history = history(3, 3);
+        history.first();
+        // The paused frame is evicted, so the cursor moves to the next one:
+        assert!(history.push(frame(4)));
+        assert_eq!(history.position(), (1, 3));
+        assert_eq!(current(&history), "_:frame2");
+        history.step_forward();
+        assert!(!history.push(frame(5)));
+        assert_eq!(history.position(), (1, 3));
+        assert_eq!(current(&history), "_:frame3");
+    }
+}
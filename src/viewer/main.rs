@@ -3,16 +3,50 @@
 #[cfg(not(feature = "std"))]
 compile_error!("asimov-image-viewer requires the 'std' feature");
 
-use asimov_image_module::core::{Error, Result as CoreResult, handle_error, warn_user_with_error};
+mod export;
+mod font;
+mod frame;
+mod grid;
+mod headless;
+mod history;
+mod overlay;
+mod pacing;
+mod split;
+mod viewport;
+
+use asimov_image_module::{
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, info_user,
+        init_error_output, record_error, warn_user_with_error,
+    },
+    jsonld::ContextOptions,
+    pixels,
+    stream::{JsonStream, Tee},
+    tally::{FailureOptions, Tally},
+    term::Protocol,
+};
 use asimov_module::SysexitsError::{self, *};
-use clap::Parser;
+use clap::{CommandFactory, Parser, ValueEnum};
 use clientele::StandardOptions;
+use frame::Frame;
+use grid::{Grid, GroupBy};
+use history::History;
 use know::classes::Image as KnowImage;
+use pacing::{FrameReceiver, FrameSender, Pacer, Queue, frame_channel};
+use serde_json::Value;
+use split::{Side, Split};
 use std::error::Error as StdError;
-use std::io::{self, BufRead, Write};
-use std::sync::mpsc::{self, Receiver};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::TryRecvError;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use viewport::{ScaleMode, Viewport};
+
+/// How long `--on-eof linger` keeps the window open by default.
+const DEFAULT_LINGER: Duration = Duration::from_secs(5);
 
 /// asimov-image-viewer
 #[derive(Debug, Parser)]
@@ -20,9 +54,204 @@ struct Options {
     #[clap(flatten)]
     flags: StandardOptions,
 
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    jsonld: ContextOptions,
+
+    #[clap(flatten)]
+    failures: FailureOptions,
+
     /// Copy stdin to stdout (pass-through / tee)
     #[arg(short = 'U', long = "union")]
     union: bool,
+
+    /// Number of received frames kept for stepping back and forth
+    #[arg(long, value_name = "FRAMES", default_value_t = 64, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    history: usize,
+
+    /// Show the latest frame of each stream side by side in a grid
+    #[arg(short = 'g', long)]
+    grid: bool,
+
+    /// Image property that assigns frames to grid tiles
+    #[arg(long, value_enum, default_value_t = GroupBy::Source)]
+    group_by: GroupBy,
+
+    /// Number of grid columns; defaults to a near-square layout
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    grid_columns: Option<usize>,
+
+    /// Playback rate for frames without a `delay` or `timestamp` property
+    #[arg(long, value_name = "RATE", value_parser = parse_fps)]
+    fps: Option<f64>,
+
+    /// Whether to show only the newest frame or every frame in order
+    #[arg(long, value_enum, default_value_t = Queue::Latest)]
+    queue: Queue,
+
+    /// Number of frames buffered between the input and the display
+    #[arg(long, value_name = "FRAMES", default_value_t = 16, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    queue_size: usize,
+
+    /// Maximum window refresh rate
+    #[arg(long, value_name = "HZ", default_value_t = 60, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    refresh_rate: usize,
+
+    /// Directory receiving screenshots saved with the S key
+    #[arg(long, value_name = "DIR", default_value = "screenshots")]
+    screenshot_dir: PathBuf,
+
+    /// Initial window size and optional position, e.g. `800x600` or `800x600+100+50`
+    #[arg(long, value_name = "WxH[+X+Y]", default_value = "320x240", value_parser = parse_geometry)]
+    geometry: Geometry,
+
+    /// Window pixel scale factor
+    #[arg(long, value_enum, default_value_t = WindowScale::X1)]
+    scale: WindowScale,
+
+    /// How images are sized to the window before zooming
+    #[arg(long, value_enum, default_value_t = ScaleMode::Fit)]
+    scale_mode: ScaleMode,
+
+    /// Keep the window above all others
+    #[arg(long)]
+    topmost: bool,
+
+    /// Open the window without decorations
+    #[arg(long)]
+    borderless: bool,
+
+    /// Color around the image, as hex RGB
+    #[arg(long, value_name = "RRGGBB", default_value = "000000", value_parser = parse_color)]
+    background: u32,
+
+    /// Window title; `{id}`, `{source}`, `{width}` and `{height}` are replaced by image fields
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = "{id} ({width}x{height})"
+    )]
+    title: String,
+
+    /// Split the view between the first two `source`s of the input
+    #[arg(short = 'c', long, conflicts_with = "grid")]
+    compare: bool,
+
+    /// Split the view between the input and frames read from FILE (e.g. a named pipe)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["grid", "compare"])]
+    compare_with: Option<PathBuf>,
+
+    /// What the window does once the input ends [default: wait, or linger with --linger]
+    #[arg(long, value_enum, value_name = "ACTION")]
+    on_eof: Option<OnEof>,
+
+    /// Seconds to keep showing the last frame with `--on-eof linger` [default: 5]
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    linger: Option<Duration>,
+
+    /// Where to display frames; `snapshot` and `terminal` need no display server
+    #[arg(short = 'b', long, value_enum, default_value_t = Backend::Window)]
+    backend: Backend,
+
+    /// Directory receiving `frame-NNNNNN.png` files with `--backend snapshot`
+    #[arg(long, value_name = "DIR", default_value = "snapshots")]
+    snapshot_dir: PathBuf,
+
+    /// Graphics protocol used with `--backend terminal`
+    #[arg(long, value_enum, default_value_t = Protocol::Halfblock)]
+    term_protocol: Protocol,
+
+    /// Shorthand for `--backend terminal`
+    #[arg(long, conflicts_with = "backend")]
+    term: bool,
+
+    /// Width in terminal columns; defaults to the terminal width, or 80 if not a terminal
+    #[arg(long, value_name = "COLS", value_parser = clap::value_parser!(u32).range(1..))]
+    term_width: Option<u32>,
+
+    /// Height in terminal rows (half-block only); defaults to the terminal height
+    #[arg(long, value_name = "ROWS", value_parser = clap::value_parser!(u32).range(1..))]
+    term_height: Option<u32>,
+}
+
+impl Options {
+    fn backend(&self) -> Backend {
+        if self.term {
+            Backend::Terminal
+        } else {
+            self.backend
+        }
+    }
+
+    /// The queuing policy of the input.
+    fn queue(&self) -> Queue {
+        // Headless backends keep up with every frame, so they never drop any,
+        // and splitting by source must not drop one side's frames for the other's:
+        if self.backend() != Backend::Window || self.compare {
+            Queue::Every
+        } else {
+            self.queue
+        }
+    }
+
+    fn on_eof(&self) -> OnEof {
+        match (self.on_eof, self.linger) {
+            (Some(on_eof), _) => on_eof,
+            (None, Some(_)) => OnEof::Linger,
+            (None, None) => OnEof::Wait,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum OnEof {
+    /// Close the window as soon as the last frame has been shown
+    Exit,
+    /// Close the window after `--linger` seconds
+    Linger,
+    /// Keep the window open until it is closed or Esc or Q is pressed
+    Wait,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Geometry {
+    width: usize,
+    height: usize,
+    position: Option<(isize, isize)>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum WindowScale {
+    X1,
+    X2,
+    X4,
+    X8,
+    /// The largest factor at which the window fits on the screen
+    FitScreen,
+}
+
+impl From<WindowScale> for minifb::Scale {
+    fn from(scale: WindowScale) -> Self {
+        match scale {
+            WindowScale::X1 => Self::X1,
+            WindowScale::X2 => Self::X2,
+            WindowScale::X4 => Self::X4,
+            WindowScale::X8 => Self::X8,
+            WindowScale::FitScreen => Self::FitScreen,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Backend {
+    /// Display frames in a desktop window
+    Window,
+    /// Save frames as PNG files
+    Snapshot,
+    /// Draw frames on stdout with terminal graphics
+    Terminal,
 }
 
 pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
@@ -47,10 +276,34 @@ pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
         return Ok(EX_OK);
     }
 
+    // The terminal backend owns stdout, so it cannot also tee the input:
+    if options.union && options.backend() == Backend::Terminal {
+        Options::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--union cannot be used with --backend terminal",
+            )
+            .exit();
+    }
+    let split = options.compare || options.compare_with.is_some();
+    if (options.grid || split) && options.backend() != Backend::Window {
+        Options::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--grid and --compare require --backend window",
+            )
+            .exit();
+    }
+
     // Configure logging & tracing:
     #[cfg(feature = "tracing")]
     asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
 
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
     let exit_code = match run_viewer(&options) {
         Ok(()) => EX_OK,
         Err(err) => handle_error(&err, &options.flags),
@@ -67,62 +320,72 @@ fn run_viewer(opts: &Options) -> CoreResult<()> {
     asimov_module::tracing::info!(
         target: "asimov_image_module::viewer",
         union = union,
+        backend = ?opts.backend(),
         "starting viewer"
     );
 
-    let (tx, rx) = mpsc::channel::<KnowImage>();
-
-    // Reader thread: stdin -> JSON lines -> KnowImage -> channel
-    let debug = flags.debug;
-    let verbose = flags.verbose;
+    let (tx, rx) = frame_channel(opts.queue(), opts.queue_size);
 
+    // Reader threads: stdin (and the compared input) -> JSON lines -> Frame -> channel
+    let tally = Arc::new(Tally::new(&opts.failures));
+    let read_opts = ReadOptions {
+        debug: flags.debug,
+        verbose: flags.verbose,
+    };
+    let stdin_tally = Arc::clone(&tally);
     thread::spawn(move || {
-        let stdin = io::stdin();
-        let mut stdout = io::stdout();
-
-        for line_res in stdin.lock().lines() {
-            match line_res {
-                Ok(line) => {
-                    if union {
-                        let _ = writeln!(stdout, "{line}");
-                        let _ = stdout.flush();
-                    }
-                    match serde_json::from_str::<KnowImage>(&line) {
-                        Ok(img) => {
-                            if tx.send(img).is_err() {
-                                break;
-                            }
-                        },
-                        Err(e) => {
-                            if debug || verbose >= 1 {
-                                eprintln!("WARN: failed to parse Image JSON-LD");
-                            }
-                            #[cfg(feature = "tracing")]
-                            asimov_module::tracing::warn!(
-                                target: "asimov_image_module::viewer",
-                                error = %e,
-                                "failed to parse Image JSON-LD"
-                            );
-                        },
-                    }
-                },
-                Err(e) => {
-                    if debug || verbose >= 1 {
-                        eprintln!("WARN: stdin read error: {e}");
-                    }
-                    #[cfg(feature = "tracing")]
-                    asimov_module::tracing::warn!(
-                        target: "asimov_image_module::viewer",
-                        error = %e,
-                        "stdin read error"
-                    );
-                    break;
-                },
-            }
-        }
+        let stdin = io::stdin().lock();
+        let input: Box<dyn BufRead> = if union {
+            Box::new(Tee::new(stdin, io::stdout()))
+        } else {
+            Box::new(stdin)
+        };
+        read_frames(input, "stdin", tx, read_opts, &stdin_tally)
     });
 
-    run_ui(rx, flags)?;
+    let right_rx = match &opts.compare_with {
+        Some(path) => {
+            let file = File::open(path).map_err(|e| Error::Io {
+                context: "opening compared input",
+                source: e,
+            })?;
+            let (tx, rx) = frame_channel(Queue::Latest, opts.queue_size);
+            let tally = Arc::clone(&tally);
+            thread::spawn(move || {
+                let input = BufReader::new(file);
+                read_frames(input, "compared input", tx, read_opts, &tally)
+            });
+            Some(rx)
+        },
+        None => None,
+    };
+
+    // Headless backends only stop at the end of the input:
+    let result = match opts.backend() {
+        Backend::Window => run_ui(rx, right_rx, opts, &tally),
+        Backend::Snapshot => headless::run_snapshots(rx, &opts.snapshot_dir, flags, &tally)
+            .map(|()| Stop::EndOfInput),
+        Backend::Terminal => {
+            let size = (opts.term_width, opts.term_height);
+            headless::run_terminal(rx, opts.term_protocol, size, flags, &tally)
+                .map(|()| Stop::EndOfInput)
+        },
+    };
+    let processed = match opts.backend() {
+        Backend::Window | Backend::Terminal => "displayed",
+        Backend::Snapshot => "saved",
+    };
+    tally.report(flags, processed);
+
+    // A reader thread may have exceeded the failure budget:
+    let stop = result?;
+    if let Some(err) = tally.take_fatal() {
+        return Err(err);
+    }
+    // Quitting before the first frame is not a lack of input:
+    if stop == Stop::EndOfInput && tally.processed() == 0 {
+        return Err(Error::NoInput(format!("no image frame was {processed}")));
+    }
 
     #[cfg(feature = "tracing")]
     asimov_module::tracing::info!(
@@ -133,12 +396,142 @@ fn run_viewer(opts: &Options) -> CoreResult<()> {
     Ok(())
 }
 
-fn run_ui(rx: Receiver<KnowImage>, flags: &StandardOptions) -> CoreResult<()> {
-    use minifb::{Key, Scale, ScaleMode, Window, WindowOptions};
+/// How a reader thread reports its input's failures.
+#[derive(Clone, Copy, Debug)]
+struct ReadOptions {
+    debug: bool,
+    verbose: u8,
+}
+
+/// Parses JSON-LD images from `input` and sends them to the display,
+/// counting them in `tally`. Returns at the end of the input, or once the
+/// failure budget is exceeded, which drops `tx` and so tells the display
+/// that the input has ended.
+fn read_frames(input: impl BufRead, name: &str, tx: FrameSender, opts: ReadOptions, tally: &Tally) {
+    let ReadOptions { debug, verbose } = opts;
+    for record in JsonStream::new(input) {
+        let record = match record {
+            Ok(record) => record,
+            Err(Error::Io { source: e, .. }) => {
+                if debug || verbose >= 1 {
+                    eprintln!("WARN: {name} read error: {e}");
+                }
+                #[cfg(feature = "tracing")]
+                asimov_module::tracing::warn!(
+                    target: "asimov_image_module::viewer",
+                    error = %e,
+                    "{name} read error"
+                );
+                break;
+            },
+            Err(e) => {
+                tally.record_read();
+                if !parse_failed(e, InputContext::default(), tally, debug || verbose >= 1) {
+                    break;
+                }
+                continue;
+            },
+        };
+        tally.record_read();
+        let frame = match Frame::from_value(&record.value) {
+            Ok(frame) => frame,
+            Err(e) => {
+                let input = InputContext {
+                    line: Some(record.line),
+                    image: record.value.get("@id").and_then(Value::as_str),
+                };
+                let error = Error::Parse {
+                    line: Some(record.line),
+                    message: e.to_string(),
+                };
+                if !parse_failed(error, input, tally, debug || verbose >= 1) {
+                    break;
+                }
+                continue;
+            },
+        };
+        tally.record_parsed();
+
+        if !tx.send(frame) {
+            break;
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::debug!(
+        target: "asimov_image_module::viewer",
+        "end of {name}"
+    );
+}
+
+/// Reports an input record that is not a valid image and counts it as failed.
+/// Returns `false`, after handing the error to the display, once the failure
+/// budget is exceeded.
+fn parse_failed(error: Error, input: InputContext, tally: &Tally, warn: bool) -> bool {
+    if warn {
+        eprintln!("WARN: failed to parse Image JSON-LD");
+    }
+    record_error("warning", "failed to parse Image JSON-LD", &error, input);
+
+    #[cfg(feature = "tracing")]
+    asimov_module::tracing::warn!(
+        target: "asimov_image_module::viewer",
+        error = %error,
+        "failed to parse Image JSON-LD"
+    );
 
-    let mut width: usize = 320;
-    let mut height: usize = 240;
+    match tally.record_failure(error) {
+        Ok(()) => true,
+        Err(error) => {
+            tally.abort(error);
+            false
+        },
+    }
+}
+
+/// Why the display stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Stop {
+    /// The user closed the window before the input ended.
+    Quit,
+    /// The input ended.
+    EndOfInput,
+}
+
+/// Displays frames in a window until it is closed or the input ends, as
+/// configured by `--on-eof`, or the failure budget is exceeded.
+fn run_ui(
+    rx: FrameReceiver,
+    right_rx: Option<FrameReceiver>,
+    opts: &Options,
+    tally: &Tally,
+) -> CoreResult<Stop> {
+    use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+
+    const ZOOM_STEP: f32 = 1.25;
+
+    let flags = &opts.flags;
+    let mut width = opts.geometry.width;
+    let mut height = opts.geometry.height;
     let mut buffer: Vec<u32> = vec![0; width * height];
+    let mut canvas: Vec<u32> = Vec::new();
+    let mut canvas_size = (0, 0);
+    let mut history = History::new(opts.history);
+    let mut pacer = Pacer::new(opts.queue(), opts.fps);
+    let mut viewport = Viewport::new(opts.scale_mode);
+    let mut drag_from: Option<(f32, f32)> = None;
+    let mut dragging_divider = false;
+    let mut show_overlays = true;
+    let mut title = String::new();
+    let mut ended_at: Option<Instant> = None;
+    let mut grid = opts
+        .grid
+        .then(|| Grid::new(opts.group_by, opts.grid_columns));
+    let mut split = if opts.compare {
+        Some(Split::by_source())
+    } else {
+        right_rx.as_ref().map(|_| Split::new())
+    };
 
     let mut window = Window::new(
         "ASIMOV",
@@ -146,65 +539,312 @@ fn run_ui(rx: Receiver<KnowImage>, flags: &StandardOptions) -> CoreResult<()> {
         height,
         WindowOptions {
             resize: true,
-            scale: Scale::X1,
-            scale_mode: ScaleMode::AspectRatioStretch,
-            topmost: false,
-            borderless: false,
+            scale: opts.scale.into(),
+            // The view is rendered at window size, so it is never scaled by minifb:
+            scale_mode: minifb::ScaleMode::UpperLeft,
+            topmost: opts.topmost,
+            borderless: opts.borderless,
             transparency: false,
             ..WindowOptions::default()
         },
     )
-    .map_err(|e| Error::Other(e.to_string()))?;
+    .map_err(|e| Error::Window(e.to_string()))?;
 
-    window.set_target_fps(60);
+    window.set_target_fps(opts.refresh_rate);
+    window.set_background_color(
+        (opts.background >> 16) as u8,
+        (opts.background >> 8) as u8,
+        opts.background as u8,
+    );
+    if let Some((x, y)) = opts.geometry.position {
+        window.set_position(x, y);
+    }
+
+    // The window's size in buffer pixels is its size divided by the scale factor:
+    let factor = (window.get_size().0 / width.max(1)).max(1);
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        let mut latest: Option<KnowImage> = None;
-        while let Ok(img) = rx.try_recv() {
-            latest = Some(img);
+    while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
+        let size = window.get_size();
+        let win = (size.0 / factor, size.1 / factor);
+
+        // A reader thread exceeded the failure budget:
+        if let Some(err) = tally.take_fatal() {
+            return Err(err);
         }
 
-        if let Some(img) = latest {
-            if let Err(e) = show_image(&mut window, &mut buffer, &mut width, &mut height, img) {
+        if let Some(grid) = grid.as_mut() {
+            let mut dirty = win != canvas_size;
+            loop {
+                match rx.try_recv() {
+                    Ok(frame) => match grid.update(&frame.image) {
+                        Ok(()) => {
+                            tally.record_processed();
+                            dirty = true;
+                        },
+                        Err(e) => {
+                            warn_user_with_error(flags, "failed to display image", &e);
+                            tally.record_failure(e)?;
+                        },
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        if ended_at.is_none() {
+                            ended_at = Some(Instant::now());
+                            dirty = true;
+                        }
+                        break;
+                    },
+                }
+            }
+
+            if dirty {
+                canvas_size = win;
+                canvas.resize(win.0 * win.1, 0);
+                grid.render(&mut canvas, win, opts.background);
+
+                let mut new_title = format!("ASIMOV [{} streams]", grid.len());
+                if ended_at.is_some() {
+                    new_title += " | end of input";
+                }
+                if new_title != title {
+                    window.set_title(&new_title);
+                    title = new_title;
+                }
+            }
+        } else {
+            let mut dirty = false;
+            while let Some(frame) = pacer.poll(&rx) {
+                match split.as_mut().map_or(Some(Side::Left), |s| s.side(&frame)) {
+                    Some(Side::Left) => {
+                        tally.record_processed();
+                        dirty |= history.push(frame);
+                    },
+                    Some(Side::Right) => {
+                        tally.record_processed();
+                        dirty = true;
+                        if let Some(split) = split.as_mut()
+                            && let Err(e) = split.set_right(&frame)
+                        {
+                            warn_user_with_error(flags, "failed to display image", &e);
+                            tally.record_failure(e)?;
+                        }
+                    },
+                    // A third source has no side to be shown on:
+                    None => tally.record_skipped(),
+                }
+            }
+            if let (Some(split), Some(right_rx)) = (split.as_mut(), &right_rx) {
+                while let Ok(frame) = right_rx.try_recv() {
+                    dirty = true;
+                    tally.record_processed();
+                    if let Err(e) = split.set_right(&frame) {
+                        warn_user_with_error(flags, "failed to display image", &e);
+                        tally.record_failure(e)?;
+                    }
+                }
+            }
+            if pacer.is_finished() && ended_at.is_none() {
+                ended_at = Some(Instant::now());
+            }
+
+            if let Some(split) = split.as_mut()
+                && window.is_key_pressed(Key::D, KeyRepeat::No)
+            {
+                split.toggle_diff();
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::O, KeyRepeat::No) {
+                show_overlays = !show_overlays;
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+                history.toggle_pause();
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+                history.step_forward();
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+                history.step_backward();
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::Home, KeyRepeat::No) {
+                history.first();
+                dirty = true;
+            }
+            if window.is_key_pressed(Key::End, KeyRepeat::No) {
+                history.last();
+                dirty = true;
+            }
+
+            if dirty
+                && let Some(frame) = history.current()
+                && let Err(e) = show_image(&mut buffer, &mut width, &mut height, &frame.image)
+            {
                 warn_user_with_error(flags, "failed to display image", &e);
+                tally.record_failure(e)?;
+            }
+
+            let img = (width, height);
+            let mouse = window.get_mouse_pos(MouseMode::Pass);
+            let cursor = mouse.unwrap_or((win.0 as f32 / 2.0, win.1 as f32 / 2.0));
+            let mut view_changed = win != canvas_size;
+
+            // Zoom around the cursor with the mouse wheel or +/-, 0 to fit, 1 for 1:1:
+            if let Some((_, scroll)) = window.get_scroll_wheel()
+                && scroll != 0.0
+            {
+                let factor = if scroll > 0.0 {
+                    ZOOM_STEP
+                } else {
+                    1.0 / ZOOM_STEP
+                };
+                viewport.zoom_by(factor, cursor, img, win);
+                view_changed = true;
+            }
+            if window.is_key_pressed(Key::Equal, KeyRepeat::Yes)
+                || window.is_key_pressed(Key::NumPadPlus, KeyRepeat::Yes)
+            {
+                viewport.zoom_by(ZOOM_STEP, cursor, img, win);
+                view_changed = true;
+            }
+            if window.is_key_pressed(Key::Minus, KeyRepeat::Yes)
+                || window.is_key_pressed(Key::NumPadMinus, KeyRepeat::Yes)
+            {
+                viewport.zoom_by(1.0 / ZOOM_STEP, cursor, img, win);
+                view_changed = true;
+            }
+            if window.is_key_pressed(Key::Key0, KeyRepeat::No) {
+                viewport.fit();
+                view_changed = true;
+            }
+            if window.is_key_pressed(Key::Key1, KeyRepeat::No) {
+                viewport.actual_size(cursor, img, win);
+                view_changed = true;
+            }
+
+            // Drag to pan, or to move the divider when grabbed:
+            if window.get_mouse_down(MouseButton::Left) {
+                if drag_from.is_none() {
+                    dragging_divider = split
+                        .as_ref()
+                        .zip(mouse)
+                        .is_some_and(|(split, pos)| split.is_near_divider(pos.0, win));
+                }
+                if dragging_divider {
+                    if let (Some(split), Some(to)) = (split.as_mut(), mouse) {
+                        split.move_divider(to.0, win);
+                        view_changed = true;
+                    }
+                } else if let (Some(from), Some(to)) = (drag_from, mouse)
+                    && from != to
+                {
+                    viewport.pan(to.0 - from.0, to.1 - from.1, img, win);
+                    view_changed = true;
+                }
+                drag_from = mouse;
+            } else {
+                drag_from = None;
+                dragging_divider = false;
+            }
+
+            if dirty || view_changed {
+                canvas_size = win;
+                canvas.resize(win.0 * win.1, 0);
+                viewport.render(&buffer, img, &mut canvas, win, opts.background);
+                if show_overlays && let Some(frame) = history.current() {
+                    overlay::draw(&frame.annotations, &viewport, img, &mut canvas, win);
+                }
+                if let Some(split) = split.as_mut() {
+                    split.render(&buffer, img, &viewport, &mut canvas, win, opts.background);
+                }
             }
+
+            // S saves the frame, Shift+S the view as displayed, J prints the frame's JSON-LD:
+            if let Some(frame) = history.current() {
+                if window.is_key_pressed(Key::S, KeyRepeat::No) {
+                    let shift =
+                        window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+                    let saved = if shift {
+                        export::save_view(&opts.screenshot_dir, &canvas, canvas_size)
+                    } else {
+                        export::save_frame(&opts.screenshot_dir, frame)
+                    };
+                    match saved {
+                        Ok(path) => info_user(flags, &format!("saved {}", path.display())),
+                        Err(e) => warn_user_with_error(flags, "failed to save screenshot", &e),
+                    }
+                }
+                if window.is_key_pressed(Key::J, KeyRepeat::No)
+                    && let Err(e) = export::print_jsonld(frame, opts.jsonld.context.as_ref())
+                {
+                    warn_user_with_error(flags, "failed to print frame", &e);
+                }
+            }
+
+            // Rebuilt on every iteration, not only when the frame changes, so that
+            // stepping while paused and frames recorded meanwhile update `[pos/len]`:
+            if let Some(current) = history.current() {
+                let mut new_title = frame_title(&opts.title, &current.image, &history);
+                if !viewport.is_fit() {
+                    new_title += &format!(" {:.0}%", viewport.scale(img, win) * 100.0);
+                }
+                new_title += &format!(" | {} fps in", rx.input_fps());
+                if let Some(latency) = pacer.latency() {
+                    new_title += &format!(", {} ms latency", latency.as_millis());
+                }
+                if let Some(pos) = mouse
+                    && let Some((x, y)) = viewport.pixel_at(pos, img, win)
+                {
+                    new_title += &pixel_status(&buffer, width, x, y);
+                }
+                if let Some(changed) = split.as_ref().and_then(Split::changed) {
+                    new_title += &format!(" | {changed:.2}% changed");
+                }
+                if ended_at.is_some() {
+                    new_title += " | end of input";
+                }
+                if new_title != title {
+                    window.set_title(&new_title);
+                    title = new_title;
+                }
+            }
+        }
+
+        if canvas.is_empty() {
+            // Minimized windows report a zero size; just keep pumping events:
+            window.update();
         } else {
             window
-                .update_with_buffer(&buffer, width, height)
-                .map_err(|e| Error::Other(e.to_string()))?;
+                .update_with_buffer(&canvas, canvas_size.0, canvas_size.1)
+                .map_err(|e| Error::Window(e.to_string()))?;
         }
 
-        std::thread::sleep(Duration::from_millis(1));
+        if let Some(ended_at) = ended_at {
+            let linger = opts.linger.unwrap_or(DEFAULT_LINGER);
+            match opts.on_eof() {
+                OnEof::Exit => break,
+                OnEof::Linger if ended_at.elapsed() >= linger => break,
+                OnEof::Linger | OnEof::Wait => {},
+            }
+        }
     }
 
-    Ok(())
+    Ok(if ended_at.is_some() {
+        Stop::EndOfInput
+    } else {
+        Stop::Quit
+    })
 }
 
 fn show_image(
-    window: &mut minifb::Window,
     buffer: &mut Vec<u32>,
     width: &mut usize,
     height: &mut usize,
-    img: KnowImage,
+    img: &KnowImage,
 ) -> CoreResult<()> {
-    let w = img
-        .width
-        .ok_or_else(|| Error::InvalidDimensions("missing image.width".into()))?;
-    let h = img
-        .height
-        .ok_or_else(|| Error::InvalidDimensions("missing image.height".into()))?;
-
-    let data = img.data;
-    let expected = w
-        .checked_mul(h)
-        .and_then(|px| px.checked_mul(3))
-        .ok_or_else(|| Error::InvalidBuffer("width*height*3 overflow".into()))?;
-    if data.len() != expected {
-        return Err(Error::InvalidBuffer(format!(
-            "byte length {} does not match width*height*3 ({expected})",
-            data.len()
-        )));
-    }
+    let (w, h) = pixels::dimensions(img)?;
 
     if *width != w || *height != h || buffer.len() != w * h {
         *width = w;
@@ -212,22 +852,133 @@ fn show_image(
         *buffer = vec![0; w * h];
     }
 
-    for (i, chunk) in data.chunks_exact(3).enumerate() {
+    for (i, chunk) in img.data.chunks_exact(3).enumerate() {
         let r = chunk[0] as u32;
         let g = chunk[1] as u32;
         let b = chunk[2] as u32;
         buffer[i] = (r << 16) | (g << 8) | b;
     }
 
-    window.set_title(&format!(
-        "{} ({}x{})",
-        img.id.unwrap_or_else(|| "ASIMOV".to_string()),
-        w,
-        h
-    ));
-    window
-        .update_with_buffer(buffer, *width, *height)
-        .map_err(|e| Error::Other(e.to_string()))?;
-
     Ok(())
 }
+
+/// Window title: the title template filled in from the image, followed by the
+/// position in the history and pause state.
+fn frame_title(template: &str, img: &KnowImage, history: &History) -> String {
+    let (pos, len) = history.position();
+    let title = template
+        .replace("{id}", img.id.as_deref().unwrap_or("ASIMOV"))
+        .replace("{source}", img.source.as_deref().unwrap_or_default())
+        .replace("{width}", &img.width.unwrap_or_default().to_string())
+        .replace("{height}", &img.height.unwrap_or_default().to_string());
+    format!(
+        "{title} [{pos}/{len}]{}",
+        if history.is_paused() { " paused" } else { "" }
+    )
+}
+
+/// Status suffix for the pixel under the cursor: coordinates and RGB value.
+fn pixel_status(buffer: &[u32], width: usize, x: usize, y: usize) -> String {
+    let px = buffer[y * width + x];
+    let (r, g, b) = ((px >> 16) & 0xff, (px >> 8) & 0xff, px & 0xff);
+    format!(" | ({x}, {y}) rgb({r}, {g}, {b}) #{r:02x}{g:02x}{b:02x}")
+}
+
+/// The lowest `--fps`, one frame every 1000 seconds.
+const MIN_FPS: f64 = 0.001;
+
+/// Accepts a finite number of frames per second of at least `MIN_FPS`, e.g.
+/// "30" or "29.97".
+fn parse_fps(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(fps) if fps.is_finite() && fps >= MIN_FPS => Ok(fps),
+        _ => Err(format!(
+            "Invalid frame rate '{s}'. Use a number of at least {MIN_FPS} (e.g., 30)"
+        )),
+    }
+}
+
+/// Accepts "800x600" or "800x600+100+50", the latter also placing the window.
+fn parse_geometry(s: &str) -> Result<Geometry, String> {
+    let invalid = || format!("Invalid geometry '{s}'. Use WxH or WxH+X+Y (e.g., 800x600+100+50)");
+    let (size, position) = match s.trim().split_once('+') {
+        Some((size, position)) => (size, Some(position)),
+        None => (s.trim(), None),
+    };
+
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: usize = width.trim().parse().map_err(|_| invalid())?;
+    let height: usize = height.trim().parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(format!("Window size {width}x{height} must not be empty"));
+    }
+
+    let position = match position {
+        Some(position) => {
+            let (x, y) = position.split_once('+').ok_or_else(invalid)?;
+            Some((
+                x.trim().parse().map_err(|_| invalid())?,
+                y.trim().parse().map_err(|_| invalid())?,
+            ))
+        },
+        None => None,
+    };
+
+    Ok(Geometry {
+        width,
+        height,
+        position,
+    })
+}
+
+/// Accepts "RRGGBB", optionally prefixed with "#".
+fn parse_color(s: &str) -> Result<u32, String> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color '{s}'. Use RRGGBB (e.g., 202020)"));
+    }
+    u32::from_str_radix(hex, 16)
+        .map_err(|_| format!("Invalid color '{s}'. Use RRGGBB (e.g., 202020)"))
+}
+
+/// Accepts a non-negative number of seconds, e.g. "5" or "0.5".
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("Invalid duration '{s}'. Use a number of seconds (e.g., 5)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn title_follows_history_moves() {
+        let mut history = History::new(8);
+        for n in 1..=3 {
+            let value = json!({
+                "@type": "Image",
+                "@id": format!("_:frame{n}"),
+                "width": 1,
+                "height": 1,
+                "data": "data:image/rgb;base64,AAAA",
+            });
+            history.push(Frame::from_value(&value).unwrap());
+        }
+        let title = |history: &History| {
+            let current = history.current().unwrap();
+            frame_title("{id} ({width}x{height})", &current.image, history)
+        };
+
+        assert_eq!(title(&history), "_:frame3 (1x1) [3/3]");
+        history.step_backward();
+        assert_eq!(title(&history), "_:frame2 (1x1) [2/3] paused");
+        history.first();
+        assert_eq!(title(&history), "_:frame1 (1x1) [1/3] paused");
+        history.last();
+        assert_eq!(title(&history), "_:frame3 (1x1) [3/3] paused");
+    }
+}
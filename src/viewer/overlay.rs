@@ -0,0 +1,248 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Annotations attached to an image, such as detections, drawn over the view.
+//!
+//! An image may carry an `annotations` array whose entries have an optional
+//! `label` and `confidence` and one shape, in image pixel coordinates:
+//!
+//! - `"box": [x, y, width, height]` or `{"x", "y", "width", "height"}`
+//! - `"polygon": [[x, y], ...]`
+//! - `"points": [[x, y], ...]` (keypoints), or a single `"point": [x, y]`
+//!
+//! Points may also be written as `{"x": .., "y": ..}`.
+
+use crate::{font, viewport::Viewport};
+use serde_json::Value;
+
+/// Colors assigned to labels, chosen by a hash of the label.
+const PALETTE: [u32; 10] = [
+    0x1f77b4, 0xff7f0e, 0x2ca02c, 0xd62728, 0x9467bd, 0x8c564b, 0xe377c2, 0x7f7f7f, 0xbcbd22,
+    0x17becf,
+];
+
+/// Outline thickness in window pixels.
+const STROKE: usize = 2;
+
+/// Side of the square marking a point, in window pixels.
+const POINT_SIZE: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub label: Option<String>,
+    pub confidence: Option<f64>,
+    pub shape: Shape,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Box {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Polygon(Vec<(f32, f32)>),
+    Points(Vec<(f32, f32)>),
+}
+
+impl Annotation {
+    /// Parses the `annotations` property of an image, skipping malformed entries.
+    pub fn parse_all(image: &Value) -> Vec<Self> {
+        image
+            .get("annotations")
+            .and_then(Value::as_array)
+            .map(|entries| entries.iter().filter_map(Self::parse).collect())
+            .unwrap_or_default()
+    }
+
+    fn parse(entry: &Value) -> Option<Self> {
+        let shape = if let Some(b) = entry.get("box") {
+            let [x, y, width, height] = match b {
+                Value::Array(v) if v.len() == 4 => {
+                    [&v[0], &v[1], &v[2], &v[3]].map(|n| n.as_f64().map(|n| n as f32))
+                },
+                _ => ["x", "y", "width", "height"]
+                    .map(|k| b.get(k).and_then(Value::as_f64).map(|n| n as f32)),
+            };
+            Shape::Box {
+                x: x?,
+                y: y?,
+                width: width?,
+                height: height?,
+            }
+        } else if let Some(p) = entry.get("polygon") {
+            Shape::Polygon(parse_points(p)?)
+        } else if let Some(p) = entry.get("points") {
+            Shape::Points(parse_points(p)?)
+        } else {
+            Shape::Points(vec![parse_point(entry.get("point")?)?])
+        };
+
+        Some(Self {
+            label: entry.get("label").and_then(Value::as_str).map(String::from),
+            confidence: entry.get("confidence").and_then(Value::as_f64),
+            shape,
+        })
+    }
+
+    /// Text drawn next to the shape, e.g. "person 93%".
+    pub fn caption(&self) -> Option<String> {
+        match (&self.label, self.confidence) {
+            (Some(label), Some(c)) => Some(format!("{label} {:.0}%", c * 100.0)),
+            (Some(label), None) => Some(label.clone()),
+            (None, Some(c)) => Some(format!("{:.0}%", c * 100.0)),
+            (None, None) => None,
+        }
+    }
+
+    /// The color of this annotation's class.
+    pub fn color(&self) -> u32 {
+        // FNV-1a, so that a label keeps its color across frames and runs:
+        let hash = self
+            .label
+            .as_deref()
+            .unwrap_or_default()
+            .bytes()
+            .fold(0x811c9dc5u32, |h, b| {
+                (h ^ u32::from(b)).wrapping_mul(0x01000193)
+            });
+        PALETTE[hash as usize % PALETTE.len()]
+    }
+}
+
+/// Draws `annotations` over `dst`, a `win`-sized buffer showing an
+/// `img`-sized image through `viewport`.
+pub fn draw(
+    annotations: &[Annotation],
+    viewport: &Viewport,
+    img: (usize, usize),
+    dst: &mut [u32],
+    win: (usize, usize),
+) {
+    let to_win = |p: (f32, f32)| viewport.window_pos(p, img, win);
+
+    for annotation in annotations {
+        let color = annotation.color();
+        let anchor = match &annotation.shape {
+            Shape::Box {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let corners = [
+                    (*x, *y),
+                    (x + width, *y),
+                    (x + width, y + height),
+                    (*x, y + height),
+                ]
+                .map(to_win);
+                draw_outline(dst, win, &corners, color);
+                corners[0]
+            },
+            Shape::Polygon(points) => {
+                let points: Vec<_> = points.iter().copied().map(to_win).collect();
+                draw_outline(dst, win, &points, color);
+                points[0]
+            },
+            Shape::Points(points) => {
+                let points: Vec<_> = points.iter().copied().map(to_win).collect();
+                for &(x, y) in &points {
+                    let half = (POINT_SIZE / 2) as f32;
+                    plot(dst, win, (x - half, y - half), POINT_SIZE, color);
+                }
+                points[0]
+            },
+        };
+
+        if let Some(caption) = annotation.caption() {
+            // Above the anchor when there is room, otherwise inside:
+            let label_h = (font::GLYPH_HEIGHT + 2) as f32;
+            let (x, y) = (anchor.0.max(0.0), anchor.1 - label_h);
+            let y = if y < 0.0 { anchor.1.max(0.0) } else { y };
+            if (x as usize) < win.0 && (y as usize) < win.1 {
+                font::draw_label(
+                    dst,
+                    win,
+                    (x as usize, y as usize),
+                    &caption,
+                    1,
+                    0x000000,
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// Draws a closed outline through `points`.
+fn draw_outline(dst: &mut [u32], win: (usize, usize), points: &[(f32, f32)], color: u32) {
+    for (i, &a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        draw_line(dst, win, a, b, color);
+    }
+}
+
+fn draw_line(dst: &mut [u32], win: (usize, usize), a: (f32, f32), b: (f32, f32), color: u32) {
+    // Only walk the visible part, which matters when zoomed far in:
+    let Some((a, b)) = clip_line(a, b, (win.0 as f32, win.1 as f32)) else {
+        return;
+    };
+    let steps = (b.0 - a.0).abs().max((b.1 - a.1).abs()).ceil().max(1.0);
+    for i in 0..=steps as usize {
+        let t = i as f32 / steps;
+        let p = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        plot(dst, win, p, STROKE, color);
+    }
+}
+
+/// Fills a `size`-pixel square with its top-left corner at `p`, clipping to the window.
+fn plot(dst: &mut [u32], win: (usize, usize), p: (f32, f32), size: usize, color: u32) {
+    let (x, y) = (p.0.floor(), p.1.floor());
+    if x + size as f32 <= 0.0 || y + size as f32 <= 0.0 {
+        return;
+    }
+    let (x0, y0) = (x.max(0.0) as usize, y.max(0.0) as usize);
+    let (w, h) = (
+        (x + size as f32) as usize - x0,
+        (y + size as f32) as usize - y0,
+    );
+    font::fill_rect(dst, win, (x0, y0), (w, h), color);
+}
+
+/// Clips the segment `a`-`b` to `[0, w] x [0, h]` (Liang-Barsky).
+fn clip_line(a: (f32, f32), b: (f32, f32), (w, h): (f32, f32)) -> Option<((f32, f32), (f32, f32))> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [(-dx, a.0), (dx, w - a.0), (-dy, a.1), (dy, h - a.1)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    (t0 <= t1).then_some((
+        (a.0 + dx * t0, a.1 + dy * t0),
+        (a.0 + dx * t1, a.1 + dy * t1),
+    ))
+}
+
+fn parse_points(value: &Value) -> Option<Vec<(f32, f32)>> {
+    let points: Option<Vec<_>> = value.as_array()?.iter().map(parse_point).collect();
+    points.filter(|p| !p.is_empty())
+}
+
+fn parse_point(value: &Value) -> Option<(f32, f32)> {
+    let (x, y) = match value {
+        Value::Array(v) if v.len() >= 2 => (&v[0], &v[1]),
+        _ => (value.get("x")?, value.get("y")?),
+    };
+    Some((x.as_f64()? as f32, y.as_f64()? as f32))
+}
@@ -0,0 +1,383 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::frame::Frame;
+use clap::ValueEnum;
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How frames waiting to be displayed are queued.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Queue {
+    /// Show the newest frame, skipping any that arrive faster than they are shown
+    Latest,
+    /// Show every frame in order, blocking the input while the queue is full
+    Every,
+}
+
+/// Creates the bounded queue between an input reader and the display.
+pub fn frame_channel(queue: Queue, size: usize) -> (FrameSender, FrameReceiver) {
+    let (tx, rx) = mpsc::sync_channel(size);
+    let parked = Arc::new(Mutex::new(None));
+    let arrivals = Arc::new(Mutex::new(VecDeque::new()));
+    let sender = FrameSender {
+        tx,
+        queue,
+        parked: Arc::clone(&parked),
+        arrivals: Arc::clone(&arrivals),
+    };
+    let receiver = FrameReceiver {
+        rx,
+        parked,
+        arrivals,
+    };
+    (sender, receiver)
+}
+
+/// The sending half of the display queue.
+///
+/// Under `Queue::Every` a full queue blocks the sender. Under `Queue::Latest`
+/// a frame that finds the queue full is parked in a one-frame slot instead,
+/// replacing any frame parked there, so the newest frame is never the one
+/// dropped. A parked frame is received after every queued one.
+#[derive(Debug)]
+pub struct FrameSender {
+    tx: SyncSender<Frame>,
+    queue: Queue,
+    parked: Arc<Mutex<Option<Frame>>>,
+    arrivals: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl FrameSender {
+    /// Queues a frame. Returns `false` once the display has gone.
+    pub fn send(&self, frame: Frame) -> bool {
+        // Count the frame as it arrives, not when the display takes it:
+        if let Ok(mut arrivals) = self.arrivals.lock() {
+            arrivals.push_back(frame.received);
+            prune_arrivals(&mut arrivals, Instant::now());
+        }
+        match self.queue {
+            // Block while the display catches up (backpressure):
+            Queue::Every => self.tx.send(frame).is_ok(),
+            Queue::Latest => {
+                let Ok(mut parked) = self.parked.lock() else {
+                    return false;
+                };
+                // This frame supersedes the parked one:
+                parked.take();
+                match self.tx.try_send(frame) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(frame)) => {
+                        #[cfg(feature = "tracing")]
+                        asimov_module::tracing::debug!(
+                            target: "asimov_image_module::viewer",
+                            "display queue full, parking frame"
+                        );
+                        *parked = Some(frame);
+                        true
+                    },
+                    Err(TrySendError::Disconnected(_)) => false,
+                }
+            },
+        }
+    }
+}
+
+/// The receiving half of the display queue.
+#[derive(Debug)]
+pub struct FrameReceiver {
+    rx: Receiver<Frame>,
+    parked: Arc<Mutex<Option<Frame>>>,
+    arrivals: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl FrameReceiver {
+    /// Frames sent during the last second, whether displayed yet or not.
+    pub fn input_fps(&self) -> usize {
+        let Ok(mut arrivals) = self.arrivals.lock() else {
+            return 0;
+        };
+        prune_arrivals(&mut arrivals, Instant::now());
+        arrivals.len()
+    }
+
+    /// Returns the next frame without blocking.
+    pub fn try_recv(&self) -> Result<Frame, TryRecvError> {
+        match self.rx.try_recv() {
+            Ok(frame) => Ok(frame),
+            Err(e) => self.take_parked().ok_or(e),
+        }
+    }
+
+    /// Waits for the next frame. Returns `None` once the input has ended.
+    pub fn recv(&self) -> Option<Frame> {
+        if let Ok(frame) = self.try_recv() {
+            return Some(frame);
+        }
+        self.rx.recv().ok().or_else(|| self.take_parked())
+    }
+
+    fn take_parked(&self) -> Option<Frame> {
+        self.parked.lock().ok()?.take()
+    }
+}
+
+impl Iterator for &FrameReceiver {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        self.recv()
+    }
+}
+
+/// Releases received frames for display according to the queuing policy and
+/// their timing: the previous frame's `delay`, else the difference between
+/// timestamps, else the fixed playback rate, else immediately.
+#[derive(Debug)]
+pub struct Pacer {
+    queue: Queue,
+    interval: Option<Duration>,
+    pending: Option<Frame>,
+    shown: Option<Shown>,
+    latency: Option<Duration>,
+    ended: bool,
+}
+
+#[derive(Debug)]
+struct Shown {
+    at: Instant,
+    timestamp: Option<f64>,
+    delay: Option<Duration>,
+}
+
+impl Pacer {
+    pub fn new(queue: Queue, fps: Option<f64>) -> Self {
+        Self {
+            queue,
+            interval: fps.and_then(|fps| Duration::try_from_secs_f64(1.0 / fps).ok()),
+            pending: None,
+            shown: None,
+            latency: None,
+            ended: false,
+        }
+    }
+
+    /// Returns the next frame if it is due for display.
+    pub fn poll(&mut self, rx: &FrameReceiver) -> Option<Frame> {
+        match self.queue {
+            Queue::Every => {
+                // Take one frame at a time so that a full channel blocks the reader:
+                if self.pending.is_none() {
+                    self.pending = self.receive(rx);
+                }
+            },
+            Queue::Latest => {
+                while let Some(frame) = self.receive(rx) {
+                    self.pending = Some(frame);
+                }
+            },
+        }
+
+        let now = Instant::now();
+        if self.due(self.pending.as_ref()?).is_none_or(|due| now < due) {
+            return None;
+        }
+
+        let frame = self.pending.take()?;
+        self.latency = Some(now.duration_since(frame.received));
+        self.shown = Some(Shown {
+            at: now,
+            timestamp: frame.timestamp,
+            delay: frame.delay,
+        });
+        Some(frame)
+    }
+
+    /// Whether the input has ended and every frame released.
+    pub fn is_finished(&self) -> bool {
+        self.ended && self.pending.is_none()
+    }
+
+    /// Time between reading the last displayed frame and displaying it.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// When the frame may be displayed; `None` if beyond the representable
+    /// future.
+    fn due(&self, frame: &Frame) -> Option<Instant> {
+        let Some(shown) = &self.shown else {
+            return Some(frame.received);
+        };
+        let wait = shown
+            .delay
+            .or_else(|| match (shown.timestamp, frame.timestamp) {
+                // A gap too large for a `Duration` counts as missing:
+                (Some(prev), Some(next)) => {
+                    Duration::try_from_secs_f64((next - prev).max(0.0)).ok()
+                },
+                _ => None,
+            })
+            .or(self.interval)
+            .unwrap_or_default();
+        shown.at.checked_add(wait)
+    }
+
+    fn receive(&mut self, rx: &FrameReceiver) -> Option<Frame> {
+        match rx.try_recv() {
+            Ok(frame) => Some(frame),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.ended = true;
+                None
+            },
+        }
+    }
+}
+
+/// Forgets arrivals older than a second.
+fn prune_arrivals(arrivals: &mut VecDeque<Instant>, now: Instant) {
+    while arrivals
+        .front()
+        .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(1))
+    {
+        arrivals.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(n: usize) -> Frame {
+        timed_frame(n, json!({}))
+    }
+
+    /// A frame with the timing properties of `timing`.
+    fn timed_frame(n: usize, timing: serde_json::Value) -> Frame {
+        let mut value = json!({
+            "@type": "Image",
+            "@id": format!("_:frame{n}"),
+            "width": 1,
+            "height": 1,
+            "data": "data:image/rgb;base64,AAAA",
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(timing.as_object().unwrap().clone());
+        Frame::from_value(&value).unwrap()
+    }
+
+    fn id(frame: &Frame) -> &str {
+        frame.image.id.as_deref().unwrap()
+    }
+
+    #[test]
+    fn latest_keeps_the_newest_frame_of_a_burst() {
+        let (tx, rx) = frame_channel(Queue::Latest, 4);
+        for n in 1..=100 {
+            assert!(tx.send(frame(n)));
+        }
+        drop(tx);
+
+        let ids: Vec<String> = (&rx).map(|f| id(&f).to_string()).collect();
+        assert_eq!(
+            ids,
+            ["_:frame1", "_:frame2", "_:frame3", "_:frame4", "_:frame100"]
+        );
+    }
+
+    #[test]
+    fn latest_drops_the_parked_frame_once_a_newer_one_is_queued() {
+        let (tx, rx) = frame_channel(Queue::Latest, 1);
+        tx.send(frame(1));
+        tx.send(frame(2)); // parked
+        assert_eq!(id(&rx.try_recv().unwrap()), "_:frame1");
+        tx.send(frame(3)); // queued, superseding the parked frame
+        assert_eq!(id(&rx.try_recv().unwrap()), "_:frame3");
+        assert_eq!(rx.try_recv().unwrap_err(), TryRecvError::Empty);
+        drop(tx);
+        assert_eq!(rx.try_recv().unwrap_err(), TryRecvError::Disconnected);
+    }
+
+    #[test]
+    fn pacer_shows_the_last_frame_of_a_burst() {
+        let (tx, rx) = frame_channel(Queue::Latest, 16);
+        for n in 1..=100 {
+            tx.send(frame(n));
+        }
+        drop(tx);
+
+        let mut pacer = Pacer::new(Queue::Latest, None);
+        let mut shown = Vec::new();
+        while let Some(frame) = pacer.poll(&rx) {
+            shown.push(id(&frame).to_string());
+        }
+        assert_eq!(shown.last().map(String::as_str), Some("_:frame100"));
+        assert!(pacer.is_finished());
+    }
+
+    #[test]
+    fn every_keeps_all_frames_in_order() {
+        let (tx, rx) = frame_channel(Queue::Every, 2);
+        let reader = std::thread::spawn(move || {
+            for n in 1..=10 {
+                assert!(tx.send(frame(n)));
+            }
+        });
+        let ids: Vec<String> = (&rx).map(|f| id(&f).to_string()).collect();
+        reader.join().unwrap();
+        assert_eq!(ids.len(), 10);
+        assert_eq!(ids[9], "_:frame10");
+    }
+
+    #[test]
+    fn out_of_range_delay_counts_as_missing() {
+        let frame = timed_frame(1, json!({"delay": 1e300}));
+        assert_eq!(frame.delay, None);
+        let frame = timed_frame(1, json!({"delay": 1500}));
+        assert_eq!(frame.delay, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn pacer_survives_a_huge_delay() {
+        let (tx, rx) = frame_channel(Queue::Every, 4);
+        // Large enough for a `Duration`, too large to add to an `Instant`:
+        tx.send(timed_frame(1, json!({"delay": 1e18})));
+        tx.send(frame(2));
+        drop(tx);
+
+        let mut pacer = Pacer::new(Queue::Every, None);
+        assert_eq!(pacer.poll(&rx).as_ref().map(id), Some("_:frame1"));
+        assert!(pacer.poll(&rx).is_none());
+        assert!(!pacer.is_finished());
+    }
+
+    #[test]
+    fn pacer_ignores_a_timestamp_gap_too_large_for_a_duration() {
+        let (tx, rx) = frame_channel(Queue::Every, 4);
+        tx.send(timed_frame(1, json!({"timestamp": 0})));
+        tx.send(timed_frame(2, json!({"timestamp": 1e300})));
+        drop(tx);
+
+        // The gap falls back to the rate, out of range too, so it is shown at once:
+        let mut pacer = Pacer::new(Queue::Every, Some(1e-300));
+        assert_eq!(pacer.poll(&rx).as_ref().map(id), Some("_:frame1"));
+        assert_eq!(pacer.poll(&rx).as_ref().map(id), Some("_:frame2"));
+        assert!(pacer.poll(&rx).is_none());
+        assert!(pacer.is_finished());
+    }
+
+    #[test]
+    fn input_rate_counts_frames_as_they_arrive() {
+        let (tx, rx) = frame_channel(Queue::Every, 8);
+        for n in 1..=5 {
+            tx.send(frame(n));
+        }
+        // None of them has been displayed yet:
+        assert_eq!(rx.input_fps(), 5);
+    }
+}
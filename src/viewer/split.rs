@@ -0,0 +1,172 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{frame::Frame, show_image, viewport::Viewport};
+use asimov_image_module::core::Result as CoreResult;
+
+/// Largest per-channel difference still considered equal when highlighting.
+const DIFF_TOLERANCE: u32 = 8;
+
+/// Distance in window pixels within which the divider can be grabbed.
+const GRAB_DISTANCE: f32 = 6.0;
+
+const DIVIDER_COLOR: u32 = 0xffffff;
+const CHANGED_COLOR: u32 = 0xff0000;
+
+/// Which half of the split view a frame belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Shows a second image over the right part of the view, beyond a movable
+/// divider, or the pixels that differ between both images.
+#[derive(Debug)]
+pub struct Split {
+    /// Stream keys of the left and right sides, when splitting by `source`.
+    sources: Option<[Option<String>; 2]>,
+    right: Vec<u32>,
+    width: usize,
+    height: usize,
+    /// Divider position as a fraction of the window width.
+    divider: f32,
+    diff: bool,
+    changed: Option<f32>,
+    scratch: Vec<u32>,
+}
+
+impl Split {
+    /// A split whose sides are assigned by the caller.
+    pub fn new() -> Self {
+        Self {
+            sources: None,
+            right: Vec::new(),
+            width: 0,
+            height: 0,
+            divider: 0.5,
+            diff: false,
+            changed: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// A split of one stream: frames of the first `source` seen go left, of
+    /// the second one right.
+    pub fn by_source() -> Self {
+        Self {
+            sources: Some([None, None]),
+            ..Self::new()
+        }
+    }
+
+    /// Assigns a frame to a side by its `source`, or `None` for frames of
+    /// any further sources. Always `Left` unless splitting by source.
+    pub fn side(&mut self, frame: &Frame) -> Option<Side> {
+        let Some(sources) = &mut self.sources else {
+            return Some(Side::Left);
+        };
+        let image = &frame.image;
+        let key = image.source.as_ref().or(image.id.as_ref());
+        for (slot, side) in sources.iter_mut().zip([Side::Left, Side::Right]) {
+            match slot {
+                Some(k) if Some(&*k) == key => return Some(side),
+                None => {
+                    *slot = key.cloned();
+                    return Some(side);
+                },
+                Some(_) => {},
+            }
+        }
+        None
+    }
+
+    /// Replaces the image on the right side.
+    pub fn set_right(&mut self, frame: &Frame) -> CoreResult<()> {
+        show_image(
+            &mut self.right,
+            &mut self.width,
+            &mut self.height,
+            &frame.image,
+        )
+    }
+
+    pub fn toggle_diff(&mut self) {
+        self.diff = !self.diff;
+    }
+
+    /// Percentage of differing pixels, while highlighting differences.
+    pub fn changed(&self) -> Option<f32> {
+        self.changed
+    }
+
+    /// Whether window column `x` is close enough to grab the divider.
+    pub fn is_near_divider(&self, x: f32, win: (usize, usize)) -> bool {
+        !self.diff && (x - self.divider * win.0 as f32).abs() <= GRAB_DISTANCE
+    }
+
+    /// Moves the divider to window column `x`.
+    pub fn move_divider(&mut self, x: f32, win: (usize, usize)) {
+        self.divider = (x / win.0.max(1) as f32).clamp(0.0, 1.0);
+    }
+
+    /// Completes `dst`, which already shows the `left` image through
+    /// `viewport`, with the right side or the highlighted differences.
+    pub fn render(
+        &mut self,
+        left: &[u32],
+        img: (usize, usize),
+        viewport: &Viewport,
+        dst: &mut [u32],
+        win: (usize, usize),
+        background: u32,
+    ) {
+        self.changed = None;
+        if self.right.is_empty() {
+            return;
+        }
+
+        if self.diff && img == (self.width, self.height) {
+            let mut changed = 0;
+            self.scratch.clear();
+            self.scratch
+                .extend(left.iter().zip(&self.right).map(|(&a, &b)| {
+                    let delta = [16, 8, 0]
+                        .map(|shift| ((a >> shift) & 0xff).abs_diff((b >> shift) & 0xff))
+                        .into_iter()
+                        .max()
+                        .unwrap_or_default();
+                    if delta > DIFF_TOLERANCE {
+                        changed += 1;
+                        CHANGED_COLOR
+                    } else {
+                        // Unchanged pixels as dimmed gray, so changes stand out:
+                        let luma =
+                            (((a >> 16) & 0xff) * 3 + ((a >> 8) & 0xff) * 6 + (a & 0xff)) / 30;
+                        (luma << 16) | (luma << 8) | luma
+                    }
+                }));
+            self.changed = Some(100.0 * changed as f32 / left.len().max(1) as f32);
+            viewport.render(&self.scratch, img, dst, win, background);
+            return;
+        }
+
+        self.scratch.resize(win.0 * win.1, 0);
+        viewport.render(
+            &self.right,
+            (self.width, self.height),
+            &mut self.scratch,
+            win,
+            background,
+        );
+        let x0 = ((self.divider * win.0 as f32) as usize).min(win.0);
+        for (row, src) in dst
+            .chunks_exact_mut(win.0.max(1))
+            .zip(self.scratch.chunks_exact(win.0.max(1)))
+        {
+            row[x0..].copy_from_slice(&src[x0..]);
+            for px in row.iter_mut().skip(x0.saturating_sub(1)).take(2) {
+                *px = DIVIDER_COLOR;
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// This is free and unencumbered software released into the public domain.
+
+use clap::ValueEnum;
+
+/// Smallest and largest zoom factors, in window pixels per image pixel.
+const MIN_ZOOM: f32 = 1.0 / 32.0;
+const MAX_ZOOM: f32 = 64.0;
+
+/// How an unzoomed image is sized to the window.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ScaleMode {
+    /// Show the whole image, as large as fits
+    #[default]
+    Fit,
+    /// Cover the whole window, cropping the image
+    Fill,
+    /// Show image pixels 1:1, centered
+    Center,
+}
+
+/// Maps an image onto the window: sized by a scale mode (fit-to-window by
+/// default), or a fixed zoom factor centered on an image coordinate.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    mode: ScaleMode,
+    /// Window pixels per image pixel; `None` sizes the image by `mode`.
+    zoom: Option<f32>,
+    /// Image coordinate shown at the center of the window when zoomed.
+    center: (f32, f32),
+}
+
+impl Viewport {
+    pub fn new(mode: ScaleMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the image is sized by the scale mode rather than zoomed.
+    pub fn is_fit(&self) -> bool {
+        self.zoom.is_none()
+    }
+
+    /// Current zoom factor for an image of size `img` in a window of size `win`.
+    pub fn scale(&self, img: (usize, usize), win: (usize, usize)) -> f32 {
+        self.zoom.unwrap_or_else(|| self.mode_scale(img, win))
+    }
+
+    /// Returns to the scale mode.
+    pub fn fit(&mut self) {
+        self.zoom = None;
+    }
+
+    /// Shows image pixels 1:1, keeping the pixel under `cursor` in place.
+    pub fn actual_size(&mut self, cursor: (f32, f32), img: (usize, usize), win: (usize, usize)) {
+        self.set_zoom(1.0, cursor, img, win);
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the pixel under `cursor` in place.
+    pub fn zoom_by(
+        &mut self,
+        factor: f32,
+        cursor: (f32, f32),
+        img: (usize, usize),
+        win: (usize, usize),
+    ) {
+        let zoom = self.scale(img, win) * factor;
+        self.set_zoom(zoom, cursor, img, win);
+    }
+
+    /// Moves the image by `(dx, dy)` window pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32, img: (usize, usize), win: (usize, usize)) {
+        if self.is_fit() {
+            // Panning a fitted image starts a zoomed view at the same scale:
+            self.zoom = Some(self.mode_scale(img, win));
+            self.center = (img.0 as f32 / 2.0, img.1 as f32 / 2.0);
+        }
+        let scale = self.scale(img, win);
+        self.center.0 -= dx / scale;
+        self.center.1 -= dy / scale;
+    }
+
+    /// Maps a window position to the image pixel underneath, if any.
+    pub fn pixel_at(
+        &self,
+        pos: (f32, f32),
+        img: (usize, usize),
+        win: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = self.image_coords(pos, img, win);
+        (x >= 0.0 && y >= 0.0 && (x as usize) < img.0 && (y as usize) < img.1)
+            .then_some((x as usize, y as usize))
+    }
+
+    /// Maps an image coordinate to its window position; the inverse of `pixel_at`.
+    pub fn window_pos(
+        &self,
+        pos: (f32, f32),
+        img: (usize, usize),
+        win: (usize, usize),
+    ) -> (f32, f32) {
+        let scale = self.scale(img, win);
+        let center = self.view_center(img);
+        (
+            win.0 as f32 / 2.0 + (pos.0 - center.0) * scale,
+            win.1 as f32 / 2.0 + (pos.1 - center.1) * scale,
+        )
+    }
+
+    /// Renders `src` (an `img`-sized 0RGB buffer) into `dst` (a `win`-sized
+    /// buffer) using nearest-neighbor sampling, filling uncovered areas with `background`.
+    pub fn render(
+        &self,
+        src: &[u32],
+        img: (usize, usize),
+        dst: &mut [u32],
+        win: (usize, usize),
+        background: u32,
+    ) {
+        let columns: Vec<Option<usize>> = (0..win.0)
+            .map(|x| {
+                let (ix, _) = self.image_coords((x as f32 + 0.5, 0.0), img, win);
+                (ix >= 0.0 && (ix as usize) < img.0).then_some(ix as usize)
+            })
+            .collect();
+
+        for (y, row) in dst.chunks_exact_mut(win.0.max(1)).enumerate().take(win.1) {
+            let (_, iy) = self.image_coords((0.0, y as f32 + 0.5), img, win);
+            if iy < 0.0 || iy as usize >= img.1 {
+                row.fill(background);
+                continue;
+            }
+            let src_row = &src[iy as usize * img.0..][..img.0];
+            for (px, column) in row.iter_mut().zip(&columns) {
+                *px = column.map_or(background, |ix| src_row[ix]);
+            }
+        }
+    }
+
+    fn set_zoom(
+        &mut self,
+        zoom: f32,
+        cursor: (f32, f32),
+        img: (usize, usize),
+        win: (usize, usize),
+    ) {
+        let anchor = self.image_coords(cursor, img, win);
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.zoom = Some(zoom);
+        self.center = (
+            anchor.0 - (cursor.0 - win.0 as f32 / 2.0) / zoom,
+            anchor.1 - (cursor.1 - win.1 as f32 / 2.0) / zoom,
+        );
+    }
+
+    fn image_coords(
+        &self,
+        pos: (f32, f32),
+        img: (usize, usize),
+        win: (usize, usize),
+    ) -> (f32, f32) {
+        let scale = self.scale(img, win);
+        let center = self.view_center(img);
+        (
+            center.0 + (pos.0 - win.0 as f32 / 2.0) / scale,
+            center.1 + (pos.1 - win.1 as f32 / 2.0) / scale,
+        )
+    }
+
+    fn mode_scale(&self, img: (usize, usize), win: (usize, usize)) -> f32 {
+        if img.0 == 0 || img.1 == 0 {
+            return 1.0;
+        }
+        let (sx, sy) = (win.0 as f32 / img.0 as f32, win.1 as f32 / img.1 as f32);
+        match self.mode {
+            ScaleMode::Fit => sx.min(sy),
+            ScaleMode::Fill => sx.max(sy),
+            ScaleMode::Center => 1.0,
+        }
+    }
+
+    fn view_center(&self, img: (usize, usize)) -> (f32, f32) {
+        if self.is_fit() {
+            (img.0 as f32 / 2.0, img.1 as f32 / 2.0)
+        } else {
+            self.center
+        }
+    }
+}
@@ -3,16 +3,24 @@
 #[cfg(not(feature = "std"))]
 compile_error!("asimov-image-writer requires the 'std' feature");
 
-use asimov_image_module::core::{
-    Error, Result as CoreResult, handle_error, info_user, warn_user_with_error,
+use asimov_image_module::{
+    codec,
+    core::{
+        Error, ErrorOptions, InputContext, Result as CoreResult, handle_error, info_user,
+        init_error_output, warn_input_error, warn_user_with_error,
+    },
+    pixels,
+    stream::{JsonStream, Tee},
+    tally::{FailureOptions, Tally},
 };
 use asimov_module::SysexitsError::{self, *};
 use clap::Parser;
 use clientele::StandardOptions;
 use know::classes::Image as KnowImage;
+use serde::Deserialize;
+use serde_json::Value;
 use std::error::Error as StdError;
-use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead};
 use std::path::PathBuf;
 
 /// asimov-image-writer
@@ -21,6 +29,12 @@ struct Options {
     #[clap(flatten)]
     flags: StandardOptions,
 
+    #[clap(flatten)]
+    errors: ErrorOptions,
+
+    #[clap(flatten)]
+    failures: FailureOptions,
+
     /// Copy stdin to stdout (pass-through / tee)
     #[arg(short = 'U', long)]
     union: bool,
@@ -57,6 +71,11 @@ pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
     #[cfg(feature = "tracing")]
     asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
 
+    // Configure structured error output:
+    if let Err(err) = init_error_output(&options.errors) {
+        return Ok(handle_error(&err, &options.flags));
+    }
+
     let exit_code = match run_writer(&options) {
         Ok(()) => EX_OK,
         Err(err) => handle_error(&err, &options.flags),
@@ -66,15 +85,12 @@ pub fn main() -> Result<SysexitsError, Box<dyn StdError>> {
 }
 
 fn run_writer(opts: &Options) -> CoreResult<()> {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
     let flags = &opts.flags;
-    let union = opts.union;
 
     #[cfg(feature = "tracing")]
     asimov_module::tracing::info!(
         target: "asimov_image_module::writer",
-        union = union,
+        union = opts.union,
         outputs = ?opts.files,
         "starting writer"
     );
@@ -83,32 +99,9 @@ fn run_writer(opts: &Options) -> CoreResult<()> {
         info_user(flags, "no output FILES provided; images will not be saved");
     }
 
-    for line_res in stdin.lock().lines() {
-        match line_res {
-            Ok(line) => {
-                if union {
-                    let _ = writeln!(stdout, "{line}");
-                    let _ = stdout.flush();
-                }
-
-                let parsed: KnowImage = match serde_json::from_str(&line) {
-                    Ok(img) => img,
-                    Err(e) => {
-                        warn_user_with_error(flags, "failed to parse Image JSON-LD", &e);
-                        continue;
-                    },
-                };
-
-                if let Err(e) = save_image_to_all(&parsed, &opts.files) {
-                    warn_user_with_error(flags, "failed to save image", &e);
-                }
-            },
-            Err(e) => {
-                warn_user_with_error(flags, "stdin read error", &e);
-                break;
-            },
-        }
-    }
+    let tally = Tally::new(&opts.failures);
+    let result = write_images(opts, &tally);
+    tally.report(flags, "saved");
 
     #[cfg(feature = "tracing")]
     asimov_module::tracing::info!(
@@ -116,49 +109,71 @@ fn run_writer(opts: &Options) -> CoreResult<()> {
         "writer exiting"
     );
 
-    Ok(())
+    result
 }
 
-fn save_image_to_all(img: &KnowImage, outputs: &[PathBuf]) -> CoreResult<()> {
-    let w = img
-        .width
-        .ok_or_else(|| Error::InvalidDimensions("missing image.width".into()))?
-        as usize;
-    let h = img
-        .height
-        .ok_or_else(|| Error::InvalidDimensions("missing image.height".into()))?
-        as usize;
-
-    let expected = w
-        .checked_mul(h)
-        .and_then(|px| px.checked_mul(3))
-        .ok_or_else(|| Error::InvalidBuffer("width*height*3 overflow".into()))?;
-
-    if img.data.len() != expected {
-        return Err(Error::InvalidBuffer(format!(
-            "byte length {} does not match width*height*3 ({expected})",
-            img.data.len()
-        )));
+/// Saves every image read from stdin, stopping early once the failure budget is exceeded.
+fn write_images(opts: &Options, tally: &Tally) -> CoreResult<()> {
+    let stdin = io::stdin().lock();
+    let flags = &opts.flags;
+
+    let input: Box<dyn BufRead> = if opts.union {
+        Box::new(Tee::new(stdin, io::stdout()))
+    } else {
+        Box::new(stdin)
+    };
+    for record in JsonStream::new(input) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e @ Error::Parse { .. }) => {
+                tally.record_read();
+                warn_user_with_error(flags, "failed to parse Image JSON-LD", &e);
+                tally.record_failure(e)?;
+                continue;
+            },
+            Err(e) => {
+                warn_user_with_error(flags, "stdin read error", &e);
+                tally.record_failure(e)?;
+                break;
+            },
+        };
+        tally.record_read();
+        let input = InputContext {
+            line: Some(record.line),
+            image: record.value.get("@id").and_then(Value::as_str),
+        };
+        let parsed = match KnowImage::deserialize(&record.value) {
+            Ok(img) => img,
+            Err(e) => {
+                warn_input_error(flags, "failed to parse Image JSON-LD", &e, input);
+                tally.record_failure(Error::Parse {
+                    line: Some(record.line),
+                    message: e.to_string(),
+                })?;
+                continue;
+            },
+        };
+        tally.record_parsed();
+
+        match save_image_to_all(&parsed, &opts.files) {
+            // Without FILES nothing was saved:
+            Ok(()) if opts.files.is_empty() => {},
+            Ok(()) => tally.record_processed(),
+            Err(e) => {
+                warn_input_error(flags, "failed to save image", &e, input);
+                tally.record_failure(e)?;
+            },
+        }
     }
 
-    let rgb_img = image::RgbImage::from_raw(w as u32, h as u32, img.data.clone())
-        .ok_or_else(|| Error::InvalidBuffer("failed to construct RgbImage from raw data".into()))?;
+    Ok(())
+}
 
-    let dyn_img = image::DynamicImage::ImageRgb8(rgb_img);
+fn save_image_to_all(img: &KnowImage, outputs: &[PathBuf]) -> CoreResult<()> {
+    let dyn_img = pixels::to_dynamic_image(img)?;
 
     for path in outputs {
-        if let Some(parent) = path.parent() {
-            if !parent.as_os_str().is_empty() {
-                fs::create_dir_all(parent).map_err(|e| Error::Io {
-                    context: "creating parent directory",
-                    source: e,
-                })?;
-            }
-        }
-
-        dyn_img
-            .save(path)
-            .map_err(|e| Error::Other(format!("saving to '{}' failed: {e}", path.display())))?;
+        codec::save(&dyn_img, path, img.id.as_deref())?;
     }
 
     Ok(())
@@ -0,0 +1,37 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Golden-file tests of the N-Triples and Turtle serializations.
+
+use asimov_image_module::rdf::{to_ntriples, to_turtle};
+use serde_json::Value;
+
+fn node(name: &str) -> Value {
+    serde_json::from_str(&golden(&format!("{name}.json"))).unwrap()
+}
+
+fn golden(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/rdf/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// An image read with `--pixels omit`, `--stats` and a path with spaces.
+#[test]
+fn image_as_ntriples() {
+    assert_eq!(to_ntriples(&node("image")), golden("image.nt"));
+}
+
+#[test]
+fn image_as_turtle() {
+    assert_eq!(to_turtle(&node("image")), golden("image.ttl"));
+}
+
+/// An image without an `@id`, with its pixels.
+#[test]
+fn blank_node_as_ntriples() {
+    assert_eq!(to_ntriples(&node("blank")), golden("blank.nt"));
+}
+
+#[test]
+fn blank_node_as_turtle() {
+    assert_eq!(to_turtle(&node("blank")), golden("blank.ttl"));
+}